@@ -0,0 +1,96 @@
+use crate::enemy::Archetype;
+
+pub const BOLT_RANGE: f32 = 6.0;
+pub const SUMMON_HP: i32 = 16;
+/// Living summons a single caster can have at once.
+pub const MAX_SUMMONS: usize = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Poisoned,
+    Burning,
+}
+
+impl Status {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Status::Poisoned => "poisoned",
+            Status::Burning => "burning",
+        }
+    }
+
+    /// What the log blames for the damage.
+    pub fn source(&self) -> &'static str {
+        match self {
+            Status::Poisoned => "poison",
+            Status::Burning => "flames",
+        }
+    }
+
+    pub fn damage(&self) -> i32 {
+        match self {
+            Status::Poisoned => 2,
+            Status::Burning => 4,
+        }
+    }
+
+    pub fn duration(&self) -> u32 {
+        match self {
+            Status::Poisoned => 8,
+            Status::Burning => 3,
+        }
+    }
+}
+
+/// Statuses on a creature with the turns each has left.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Statuses(Vec<(Status, u32)>);
+
+impl Statuses {
+    /// Applies `status`, restarting its duration if it is already there.
+    pub fn afflict(&mut self, status: Status) {
+        self.0.retain(|(s, _)| *s != status);
+        self.0.push((status, status.duration()));
+    }
+
+    /// Counts a turn down and returns the statuses that hurt this turn.
+    pub fn tick(&mut self) -> Vec<Status> {
+        let active = self.0.iter().map(|(s, _)| *s).collect();
+        for (_, turns) in &mut self.0 {
+            *turns -= 1;
+        }
+        self.0.retain(|(_, turns)| *turns > 0);
+        active
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.0.iter().map(|(s, _)| s.name())
+    }
+}
+
+/// Something a creature does at a distance, resolved by `State::cast` for the player and
+/// enemies alike.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Ability {
+    /// A projectile that hits the first creature on its line of fire.
+    Bolt {
+        damage: i32,
+        range: f32,
+        status: Option<Status>,
+    },
+    /// Heals wounded allies around the caster.
+    Mend { amount: i32, radius: f32 },
+    /// Calls a new enemy next to the caster.
+    Summon(Archetype),
+}
+
+impl Ability {
+    /// Turns before an enemy can use it again.
+    pub fn cooldown(&self) -> u32 {
+        match self {
+            Ability::Bolt { .. } => 0,
+            Ability::Mend { .. } => 8,
+            Ability::Summon(_) => 30,
+        }
+    }
+}
@@ -0,0 +1,470 @@
+use std::fmt;
+
+use raylib::{
+    ffi::{
+        GamepadButton::{self, *},
+        KeyboardKey::{self, *},
+    },
+    prelude::key_from_i32,
+    RaylibHandle,
+};
+
+use crate::config::{config_dir, read_pairs, write_pairs};
+
+const BINDINGS_FILE: &str = "keys.cfg";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    MoveUpLeft,
+    MoveUpRight,
+    MoveDownLeft,
+    MoveDownRight,
+    Attack,
+    Use,
+    CycleTarget,
+    NextItem,
+    Equip(usize),
+    PickUp,
+    Inspect,
+    Search,
+    Disarm,
+    Fire,
+    Explore,
+    TravelExit,
+    TravelTeleporter,
+    ZoomOut,
+    ZoomIn,
+    ZoomReset,
+    Fullscreen,
+    ToggleDebug,
+    SkipTurn,
+    Help,
+    MessageLog,
+    Pause,
+    ExportLog,
+    NewRun,
+    Erase,
+}
+
+impl GameAction {
+    pub const ALL: [GameAction; 36] = [
+        GameAction::MoveUp,
+        GameAction::MoveDown,
+        GameAction::MoveLeft,
+        GameAction::MoveRight,
+        GameAction::MoveUpLeft,
+        GameAction::MoveUpRight,
+        GameAction::MoveDownLeft,
+        GameAction::MoveDownRight,
+        GameAction::Attack,
+        GameAction::Use,
+        GameAction::CycleTarget,
+        GameAction::NextItem,
+        GameAction::Equip(0),
+        GameAction::Equip(1),
+        GameAction::Equip(2),
+        GameAction::Equip(3),
+        GameAction::PickUp,
+        GameAction::Inspect,
+        GameAction::Search,
+        GameAction::Disarm,
+        GameAction::Fire,
+        GameAction::Explore,
+        GameAction::TravelExit,
+        GameAction::TravelTeleporter,
+        GameAction::ZoomOut,
+        GameAction::ZoomIn,
+        GameAction::ZoomReset,
+        GameAction::Fullscreen,
+        GameAction::ToggleDebug,
+        GameAction::SkipTurn,
+        GameAction::Help,
+        GameAction::MessageLog,
+        GameAction::Pause,
+        GameAction::ExportLog,
+        GameAction::NewRun,
+        GameAction::Erase,
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            GameAction::MoveUp => "move_up".into(),
+            GameAction::MoveDown => "move_down".into(),
+            GameAction::MoveLeft => "move_left".into(),
+            GameAction::MoveRight => "move_right".into(),
+            GameAction::MoveUpLeft => "move_up_left".into(),
+            GameAction::MoveUpRight => "move_up_right".into(),
+            GameAction::MoveDownLeft => "move_down_left".into(),
+            GameAction::MoveDownRight => "move_down_right".into(),
+            GameAction::Attack => "attack".into(),
+            GameAction::Use => "use".into(),
+            GameAction::CycleTarget => "cycle_target".into(),
+            GameAction::NextItem => "next_item".into(),
+            GameAction::Equip(i) => format!("equip_{}", i + 1),
+            GameAction::PickUp => "pick_up".into(),
+            GameAction::Inspect => "inspect".into(),
+            GameAction::Search => "search".into(),
+            GameAction::Disarm => "disarm".into(),
+            GameAction::Fire => "fire".into(),
+            GameAction::Explore => "explore".into(),
+            GameAction::TravelExit => "travel_exit".into(),
+            GameAction::TravelTeleporter => "travel_teleporter".into(),
+            GameAction::ZoomOut => "zoom_out".into(),
+            GameAction::ZoomIn => "zoom_in".into(),
+            GameAction::ZoomReset => "zoom_reset".into(),
+            GameAction::Fullscreen => "fullscreen".into(),
+            GameAction::ToggleDebug => "toggle_debug".into(),
+            GameAction::SkipTurn => "skip_turn".into(),
+            GameAction::Help => "help".into(),
+            GameAction::MessageLog => "message_log".into(),
+            GameAction::Pause => "pause".into(),
+            GameAction::ExportLog => "export_log".into(),
+            GameAction::NewRun => "new_run".into(),
+            GameAction::Erase => "erase".into(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    pub fn delta(&self) -> Option<(isize, isize)> {
+        match self {
+            GameAction::MoveUp => Some((0, -1)),
+            GameAction::MoveDown => Some((0, 1)),
+            GameAction::MoveLeft => Some((-1, 0)),
+            GameAction::MoveRight => Some((1, 0)),
+            GameAction::MoveUpLeft => Some((-1, -1)),
+            GameAction::MoveUpRight => Some((1, -1)),
+            GameAction::MoveDownLeft => Some((-1, 1)),
+            GameAction::MoveDownRight => Some((1, 1)),
+            _ => None,
+        }
+    }
+
+    fn default_inputs(&self) -> Vec<Input> {
+        let (keys, buttons) = match self {
+            GameAction::MoveUp => (
+                vec![KEY_W, KEY_UP, KEY_K, KEY_KP_8],
+                vec![GAMEPAD_BUTTON_LEFT_FACE_UP],
+            ),
+            GameAction::MoveDown => (
+                vec![KEY_S, KEY_DOWN, KEY_J, KEY_KP_2],
+                vec![GAMEPAD_BUTTON_LEFT_FACE_DOWN],
+            ),
+            GameAction::MoveLeft => (
+                vec![KEY_A, KEY_LEFT, KEY_H, KEY_KP_4],
+                vec![GAMEPAD_BUTTON_LEFT_FACE_LEFT],
+            ),
+            GameAction::MoveRight => (
+                vec![KEY_D, KEY_RIGHT, KEY_L, KEY_KP_6],
+                vec![GAMEPAD_BUTTON_LEFT_FACE_RIGHT],
+            ),
+            GameAction::MoveUpLeft => (vec![KEY_Y, KEY_KP_7], vec![]),
+            GameAction::MoveUpRight => (vec![KEY_U, KEY_KP_9], vec![]),
+            GameAction::MoveDownLeft => (vec![KEY_B, KEY_KP_1], vec![]),
+            GameAction::MoveDownRight => (vec![KEY_N, KEY_KP_3], vec![]),
+            GameAction::Attack => (
+                vec![KEY_P, KEY_ENTER, KEY_KP_ENTER],
+                vec![GAMEPAD_BUTTON_RIGHT_FACE_DOWN],
+            ),
+            GameAction::Use => (vec![KEY_V], vec![GAMEPAD_BUTTON_RIGHT_FACE_RIGHT]),
+            GameAction::CycleTarget => (vec![KEY_TAB], vec![GAMEPAD_BUTTON_RIGHT_TRIGGER_1]),
+            GameAction::NextItem => (vec![KEY_C], vec![GAMEPAD_BUTTON_LEFT_TRIGGER_1]),
+            GameAction::Equip(0) => (vec![KEY_ONE], vec![]),
+            GameAction::Equip(1) => (vec![KEY_TWO], vec![]),
+            GameAction::Equip(2) => (vec![KEY_THREE], vec![]),
+            GameAction::Equip(3) => (vec![KEY_FOUR], vec![]),
+            GameAction::Equip(_) => (vec![], vec![]),
+            GameAction::PickUp => (vec![KEY_G], vec![GAMEPAD_BUTTON_RIGHT_FACE_LEFT]),
+            GameAction::Inspect => (vec![KEY_I], vec![]),
+            GameAction::Search => (vec![KEY_Z], vec![]),
+            GameAction::Disarm => (vec![KEY_R], vec![]),
+            GameAction::Fire => (vec![KEY_SEMICOLON], vec![]),
+            GameAction::Explore => (vec![KEY_E], vec![GAMEPAD_BUTTON_RIGHT_FACE_UP]),
+            GameAction::TravelExit => (vec![KEY_X], vec![]),
+            GameAction::MessageLog => (vec![KEY_M], vec![]),
+            GameAction::Pause => (vec![KEY_ESCAPE], vec![GAMEPAD_BUTTON_MIDDLE_RIGHT]),
+            GameAction::ExportLog => (vec![KEY_X], vec![GAMEPAD_BUTTON_RIGHT_FACE_UP]),
+            GameAction::NewRun => (vec![KEY_R], vec![GAMEPAD_BUTTON_RIGHT_FACE_UP]),
+            GameAction::Erase => (vec![KEY_BACKSPACE], vec![GAMEPAD_BUTTON_RIGHT_FACE_LEFT]),
+            GameAction::TravelTeleporter => (vec![KEY_T], vec![GAMEPAD_BUTTON_LEFT_THUMB]),
+            GameAction::ZoomOut => (vec![KEY_MINUS], vec![GAMEPAD_BUTTON_LEFT_TRIGGER_2]),
+            GameAction::ZoomIn => (vec![KEY_EQUAL], vec![GAMEPAD_BUTTON_RIGHT_TRIGGER_2]),
+            GameAction::ZoomReset => (vec![KEY_Q], vec![]),
+            GameAction::Fullscreen => (vec![KEY_F], vec![]),
+            GameAction::ToggleDebug => (vec![KEY_SPACE], vec![]),
+            GameAction::SkipTurn => (vec![KEY_O], vec![]),
+            GameAction::Help => {
+                return vec![
+                    Input::Key(KeyChord::new(KEY_F1)),
+                    Input::Key(KeyChord {
+                        shift: true,
+                        ..KeyChord::new(KEY_SLASH)
+                    }),
+                    Input::Pad(GAMEPAD_BUTTON_MIDDLE_LEFT),
+                ]
+            }
+        };
+        keys.into_iter()
+            .map(|key| Input::Key(KeyChord::new(key)))
+            .chain(buttons.into_iter().map(Input::Pad))
+            .collect()
+    }
+}
+
+const PAD_PREFIX: &str = "Pad:";
+const PAD_BUTTONS: [GamepadButton; 17] = [
+    GAMEPAD_BUTTON_LEFT_FACE_UP,
+    GAMEPAD_BUTTON_LEFT_FACE_RIGHT,
+    GAMEPAD_BUTTON_LEFT_FACE_DOWN,
+    GAMEPAD_BUTTON_LEFT_FACE_LEFT,
+    GAMEPAD_BUTTON_RIGHT_FACE_UP,
+    GAMEPAD_BUTTON_RIGHT_FACE_RIGHT,
+    GAMEPAD_BUTTON_RIGHT_FACE_DOWN,
+    GAMEPAD_BUTTON_RIGHT_FACE_LEFT,
+    GAMEPAD_BUTTON_LEFT_TRIGGER_1,
+    GAMEPAD_BUTTON_LEFT_TRIGGER_2,
+    GAMEPAD_BUTTON_RIGHT_TRIGGER_1,
+    GAMEPAD_BUTTON_RIGHT_TRIGGER_2,
+    GAMEPAD_BUTTON_MIDDLE_LEFT,
+    GAMEPAD_BUTTON_MIDDLE,
+    GAMEPAD_BUTTON_MIDDLE_RIGHT,
+    GAMEPAD_BUTTON_LEFT_THUMB,
+    GAMEPAD_BUTTON_RIGHT_THUMB,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Input {
+    Key(KeyChord),
+    Pad(GamepadButton),
+}
+
+impl Input {
+    pub fn parse(text: &str) -> Option<Self> {
+        match text.strip_prefix(PAD_PREFIX) {
+            Some(name) => PAD_BUTTONS
+                .into_iter()
+                .find(|&button| button_name(button).eq_ignore_ascii_case(name.trim()))
+                .map(Input::Pad),
+            None => KeyChord::parse(text).map(Input::Key),
+        }
+    }
+}
+
+impl fmt::Display for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Key(chord) => write!(f, "{}", chord),
+            Input::Pad(button) => write!(f, "{}{}", PAD_PREFIX, button_name(*button)),
+        }
+    }
+}
+
+fn button_name(button: GamepadButton) -> String {
+    let name = format!("{:?}", button);
+    name.trim_start_matches("GAMEPAD_BUTTON_").to_string()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyChord {
+    pub key: KeyboardKey,
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl KeyChord {
+    pub fn new(key: KeyboardKey) -> Self {
+        Self {
+            key,
+            shift: false,
+            ctrl: false,
+            alt: false,
+        }
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = key_by_name(parts.pop()?)?;
+        let mut chord = Self::new(key);
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "shift" => chord.shift = true,
+                "ctrl" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                _ => return None,
+            }
+        }
+        Some(chord)
+    }
+
+    pub fn modifiers_held(&self, rl: &RaylibHandle) -> bool {
+        let held = |left, right| rl.is_key_down(left) || rl.is_key_down(right);
+        self.shift == held(KEY_LEFT_SHIFT, KEY_RIGHT_SHIFT)
+            && self.ctrl == held(KEY_LEFT_CONTROL, KEY_RIGHT_CONTROL)
+            && self.alt == held(KEY_LEFT_ALT, KEY_RIGHT_ALT)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        write!(f, "{}", key_name(self.key))
+    }
+}
+
+fn key_name(key: KeyboardKey) -> String {
+    let name = format!("{:?}", key);
+    name.trim_start_matches("KEY_").to_string()
+}
+
+fn key_by_name(name: &str) -> Option<KeyboardKey> {
+    (0..512)
+        .filter_map(key_from_i32)
+        .find(|&key| key_name(key).eq_ignore_ascii_case(name))
+}
+
+#[derive(Debug)]
+pub struct Bindings {
+    bindings: Vec<(GameAction, Vec<Input>)>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            bindings: GameAction::ALL
+                .iter()
+                .map(|action| (*action, action.default_inputs()))
+                .collect(),
+        }
+    }
+}
+
+impl Bindings {
+    pub fn load() -> Self {
+        let mut bindings = Self::default();
+        let Some(path) = config_dir().map(|dir| dir.join(BINDINGS_FILE)) else {
+            return bindings;
+        };
+        let Some(pairs) = read_pairs(&path) else {
+            let header = "# action = key, modifier+key, Pad:button\n";
+            if let Err(e) = write_pairs(&path, header, &bindings.pairs()) {
+                eprintln!("Could not write {}: {}", path.display(), e);
+            }
+            return bindings;
+        };
+        for (name, inputs) in pairs {
+            let Some(action) = GameAction::from_name(&name) else {
+                eprintln!("Unknown action in {}: {}", path.display(), name);
+                continue;
+            };
+            let inputs: Vec<Input> = inputs
+                .split(',')
+                .map(str::trim)
+                .filter(|input| !input.is_empty())
+                .filter_map(|input| {
+                    let parsed = Input::parse(input);
+                    if parsed.is_none() {
+                        eprintln!("Unknown input for {}: {}", name, input);
+                    }
+                    parsed
+                })
+                .collect();
+            bindings.set(action, inputs);
+        }
+        bindings
+    }
+
+    fn pairs(&self) -> Vec<(String, String)> {
+        self.bindings
+            .iter()
+            .map(|(action, inputs)| (action.name(), join(inputs)))
+            .collect()
+    }
+
+    pub fn set(&mut self, action: GameAction, inputs: Vec<Input>) {
+        if let Some((_, bound)) = self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            *bound = inputs;
+        }
+    }
+
+    fn inputs(&self, action: GameAction) -> &[Input] {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map_or(&[], |(_, inputs)| inputs)
+    }
+
+    pub fn keys(&self, action: GameAction) -> impl Iterator<Item = &KeyChord> {
+        self.inputs(action).iter().filter_map(|input| match input {
+            Input::Key(chord) => Some(chord),
+            Input::Pad(_) => None,
+        })
+    }
+
+    pub fn buttons(&self, action: GameAction) -> impl Iterator<Item = GamepadButton> + '_ {
+        self.inputs(action).iter().filter_map(|input| match input {
+            Input::Key(_) => None,
+            Input::Pad(button) => Some(*button),
+        })
+    }
+
+    pub fn action(&self, rl: &RaylibHandle, key: KeyboardKey) -> Option<GameAction> {
+        self.bindings
+            .iter()
+            .find(|(_, inputs)| {
+                inputs.iter().any(|input| {
+                    matches!(input, Input::Key(chord) if chord.key == key && chord.modifiers_held(rl))
+                })
+            })
+            .map(|(action, _)| *action)
+    }
+
+    pub fn pad_action(&self, rl: &RaylibHandle, gamepad: i32) -> Option<GameAction> {
+        self.bindings
+            .iter()
+            .find(|(_, inputs)| {
+                inputs.iter().any(|input| {
+                    matches!(input, Input::Pad(button) if rl.is_gamepad_button_pressed(gamepad, *button))
+                })
+            })
+            .map(|(action, _)| *action)
+    }
+
+    pub fn label(&self, action: GameAction) -> String {
+        self.keys(action)
+            .next()
+            .map_or("-".to_string(), |chord| chord.to_string())
+    }
+
+    pub fn help(&self) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|(_, inputs)| !inputs.is_empty())
+            .map(|(action, inputs)| {
+                format!("{}: {}", action.name().replace('_', " "), join(inputs))
+            })
+            .collect()
+    }
+}
+
+fn join(inputs: &[Input]) -> String {
+    inputs
+        .iter()
+        .map(|input| input.to_string())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
@@ -0,0 +1,212 @@
+use std::collections::HashMap;
+
+use crate::{
+    ability::{Ability, Status},
+    enemy::Archetype,
+    item::{Action, Item, ItemType},
+    state::FINAL_DEPTH,
+    terrain::Terrain,
+};
+
+/// Tiles of the lair turned into the phase's hazard when it starts.
+pub const HAZARD_TILES: usize = 6;
+
+pub struct Phase {
+    /// Percentage of max HP below which this phase starts.
+    pub below: i32,
+    pub abilities: &'static [Ability],
+    /// Allies called in as the phase starts.
+    pub adds: u32,
+    pub hazard: Option<Terrain>,
+    pub announce: &'static str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BossKind {
+    Warlord,
+    EmberLord,
+    Lich,
+}
+
+/// Which boss, and how far into the fight it is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Boss {
+    pub kind: BossKind,
+    pub phase: usize,
+}
+
+impl BossKind {
+    pub fn for_depth(depth: u32) -> Option<Self> {
+        match depth {
+            3 => Some(BossKind::Warlord),
+            6 => Some(BossKind::EmberLord),
+            FINAL_DEPTH => Some(BossKind::Lich),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BossKind::Warlord => "Goblin Warlord",
+            BossKind::EmberLord => "Ember Lord",
+            BossKind::Lich => "Lich",
+        }
+    }
+
+    /// The archetype it moves, senses and fights like.
+    pub fn archetype(&self) -> Archetype {
+        match self {
+            BossKind::Warlord => Archetype::Goblin,
+            BossKind::EmberLord => Archetype::Imp,
+            BossKind::Lich => Archetype::Ghost,
+        }
+    }
+
+    pub fn hp(&self) -> i32 {
+        match self {
+            BossKind::Warlord => 120,
+            BossKind::EmberLord => 160,
+            BossKind::Lich => 220,
+        }
+    }
+
+    pub fn phases(&self) -> &'static [Phase] {
+        match self {
+            BossKind::Warlord => &[
+                Phase {
+                    below: 100,
+                    abilities: &[],
+                    adds: 0,
+                    hazard: None,
+                    announce: "The Goblin Warlord bellows a challenge",
+                },
+                Phase {
+                    below: 60,
+                    abilities: &[Ability::Summon(Archetype::Goblin)],
+                    adds: 2,
+                    hazard: None,
+                    announce: "The Goblin Warlord calls for its guards",
+                },
+                Phase {
+                    below: 30,
+                    abilities: &[Ability::Summon(Archetype::Goblin)],
+                    adds: 0,
+                    hazard: Some(Terrain::Rubble),
+                    announce: "The Goblin Warlord brings the ceiling down",
+                },
+            ],
+            BossKind::EmberLord => &[
+                Phase {
+                    below: 100,
+                    abilities: &[Ability::Bolt {
+                        damage: 8,
+                        range: 7.0,
+                        status: Some(Status::Burning),
+                    }],
+                    adds: 0,
+                    hazard: None,
+                    announce: "The Ember Lord flares up",
+                },
+                Phase {
+                    below: 50,
+                    abilities: &[
+                        Ability::Bolt {
+                            damage: 10,
+                            range: 7.0,
+                            status: Some(Status::Burning),
+                        },
+                        Ability::Summon(Archetype::Imp),
+                    ],
+                    adds: 1,
+                    hazard: Some(Terrain::Lava),
+                    announce: "Lava bursts through the floor",
+                },
+            ],
+            BossKind::Lich => &[
+                Phase {
+                    below: 100,
+                    abilities: &[Ability::Bolt {
+                        damage: 8,
+                        range: 8.0,
+                        status: Some(Status::Poisoned),
+                    }],
+                    adds: 0,
+                    hazard: None,
+                    announce: "The Lich rises from its throne",
+                },
+                Phase {
+                    below: 66,
+                    abilities: &[
+                        Ability::Mend {
+                            amount: 15,
+                            radius: 6.0,
+                        },
+                        Ability::Summon(Archetype::Ghost),
+                        Ability::Bolt {
+                            damage: 10,
+                            range: 8.0,
+                            status: Some(Status::Poisoned),
+                        },
+                    ],
+                    adds: 2,
+                    hazard: Some(Terrain::DeepWater),
+                    announce: "The Lich raises the dead",
+                },
+                Phase {
+                    below: 33,
+                    abilities: &[
+                        Ability::Summon(Archetype::Ghost),
+                        Ability::Bolt {
+                            damage: 14,
+                            range: 8.0,
+                            status: Some(Status::Poisoned),
+                        },
+                    ],
+                    adds: 1,
+                    hazard: Some(Terrain::Lava),
+                    announce: "The Lich's crypt starts to burn",
+                },
+            ],
+        }
+    }
+
+    /// Always dropped on death.
+    pub fn loot(&self) -> Item {
+        match self {
+            BossKind::Warlord => Item::new(
+                "Warlord's Cleaver".into(),
+                ItemType::Melee,
+                HashMap::from([(Action::Damage, 45)]),
+            ),
+            BossKind::EmberLord => Item::new(
+                "Ember Staff".into(),
+                ItemType::Wand(Some(Status::Burning)),
+                HashMap::from([(Action::Damage, 30)]),
+            ),
+            BossKind::Lich => Item::new(
+                "Lich's Elixir".into(),
+                ItemType::HealPotion,
+                HashMap::from([(Action::Heal, 100)]),
+            ),
+        }
+    }
+}
+
+impl Boss {
+    pub fn new(kind: BossKind) -> Self {
+        Self { kind, phase: 0 }
+    }
+
+    pub fn current(&self) -> &'static Phase {
+        &self.kind.phases()[self.phase]
+    }
+
+    /// The phase the boss should be in at `hp` out of `max_hp`.
+    pub fn phase_at(&self, hp: i32, max_hp: i32) -> usize {
+        self.kind
+            .phases()
+            .iter()
+            .rposition(|phase| hp * 100 < max_hp * phase.below)
+            .unwrap_or(0)
+    }
+}
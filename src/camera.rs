@@ -0,0 +1,81 @@
+use raylib::prelude::Vector2;
+
+use crate::{
+    events::{GameEvent, Subscriber},
+    utils::Pos,
+};
+
+const SMOOTHING: f32 = 10.0;
+
+#[derive(Debug)]
+pub struct Camera {
+    pub center: Vector2,
+    pub zoom: f32,
+    pub dead_zone: f32,
+    pub clamp: bool,
+    snap: bool,
+}
+
+impl Camera {
+    pub fn new(zoom: f32) -> Self {
+        Self {
+            center: Vector2::new(0.0, 0.0),
+            zoom,
+            dead_zone: 0.0,
+            clamp: false,
+            snap: true,
+        }
+    }
+
+    pub fn snap(&mut self) {
+        self.snap = true;
+    }
+
+    pub fn screen_to_tile(&self, screen: Vector2, midpoint: Vector2) -> Pos {
+        Pos(
+            ((screen.x - midpoint.x) / self.zoom + self.center.x).floor() as isize,
+            ((screen.y - midpoint.y) / self.zoom + self.center.y).floor() as isize,
+        )
+    }
+
+    pub fn update(&mut self, target: Vector2, zoom: f32, bounds: Vector2, view: Vector2, dt: f32) {
+        if self.snap {
+            self.snap = false;
+            self.center = target;
+            self.zoom = zoom;
+        }
+        let mut goal = self.center;
+        let (dx, dy) = (target.x - goal.x, target.y - goal.y);
+        if dx.abs() > self.dead_zone {
+            goal.x = target.x - self.dead_zone * dx.signum();
+        }
+        if dy.abs() > self.dead_zone {
+            goal.y = target.y - self.dead_zone * dy.signum();
+        }
+        let t = 1.0 - (-SMOOTHING * dt).exp();
+        self.center.x += (goal.x - self.center.x) * t;
+        self.center.y += (goal.y - self.center.y) * t;
+        self.zoom += (zoom - self.zoom) * t;
+        if self.clamp {
+            self.center.x = clamp_axis(self.center.x, bounds.x, view.x / 2.0 / self.zoom);
+            self.center.y = clamp_axis(self.center.y, bounds.y, view.y / 2.0 / self.zoom);
+        }
+    }
+}
+
+fn clamp_axis(center: f32, size: f32, half_view: f32) -> f32 {
+    if size <= half_view * 2.0 {
+        size / 2.0
+    } else {
+        center.clamp(half_view, size - half_view)
+    }
+}
+
+impl Subscriber for Camera {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Teleport { .. } | GameEvent::Descend { .. } => self.snap(),
+            _ => {}
+        }
+    }
+}
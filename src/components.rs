@@ -1,6 +1,6 @@
 use raylib::prelude::{RaylibTexture2D, Rectangle, Texture2D, Vector2};
 
-use crate::sprite_sheet::SpriteSheet;
+use crate::{camera::Camera, sprite_sheet::SpriteSheet, utils::Pos};
 #[allow(dead_code)]
 pub struct GameComponents<'a> {
     pub tex: &'a Texture2D,
@@ -25,8 +25,15 @@ pub struct GameComponents<'a> {
     pub origin: Vector2,
     pub rotation: f32,
     pub vfactor: isize,
+    pub camera: Camera,
     pub debug: bool,
     pub active_turn: bool,
+    pub auto_pickup: bool,
+    /// Index into the known teleporters while picking a travel destination.
+    pub picking_teleporter: Option<usize>,
+    pub look: Option<Pos>,
+    pub show_help: bool,
+    pub target: usize,
     pub screen_size: Vector2,
     pub midpoint: Vector2,
 }
@@ -64,8 +71,13 @@ impl<'a> GameComponents<'a> {
         let origin = Vector2::new(0.0, 0.0);
         let rotation = 0.0;
         let vfactor = 32;
+        let camera = Camera::new(vfactor as f32);
         let debug = false;
         let active_turn = false;
+        let auto_pickup = true;
+        let picking_teleporter = None;
+        let show_help = false;
+        let target = 0;
         let midpoint = Vector2::new(screen_size.x / 2.0, screen_size.y / 2.0);
         Self {
             tex,
@@ -92,10 +104,21 @@ impl<'a> GameComponents<'a> {
             origin,
             rotation,
             vfactor,
+            camera,
             debug,
             active_turn,
+            auto_pickup,
+            picking_teleporter,
+            look: None,
+            show_help,
+            target,
             screen_size,
             midpoint,
         }
     }
+
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.screen_size = Vector2::new(width, height);
+        self.midpoint = Vector2::new(width / 2.0, height / 2.0);
+    }
 }
@@ -0,0 +1,59 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const APP_NAME: &str = "roguer-ebert";
+
+pub fn config_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join(APP_NAME))
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    let base = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+    Some(base.join(APP_NAME))
+}
+
+/// `dir/stem.txt`, or `dir/stem-2.txt` and up if that file already exists.
+pub fn unique_path(dir: &Path, stem: &str) -> PathBuf {
+    let mut path = dir.join(format!("{}.txt", stem));
+    let mut n = 2;
+    while path.exists() {
+        path = dir.join(format!("{}-{}.txt", stem, n));
+        n += 1;
+    }
+    path
+}
+
+pub fn read_pairs(path: &Path) -> Option<Vec<(String, String)>> {
+    let content = fs::read_to_string(path).ok()?;
+    Some(
+        content
+            .lines()
+            .map(|line| line.split('#').next().unwrap_or_default().trim())
+            .filter(|line| !line.is_empty())
+            .filter_map(|line| {
+                let (key, value) = line.split_once('=')?;
+                Some((key.trim().to_string(), value.trim().to_string()))
+            })
+            .collect(),
+    )
+}
+
+pub fn write_pairs(path: &Path, header: &str, pairs: &[(String, String)]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut content = String::from(header);
+    for (key, value) in pairs {
+        content += &format!("{} = {}\n", key, value);
+    }
+    fs::write(path, content)
+}
@@ -0,0 +1,89 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::utils::Pos;
+
+/// Tenths applied to the inverted distances of a flee map.
+const FLEE_WEIGHT: u32 = 12;
+
+#[derive(Debug, Default, Clone)]
+pub struct DijkstraMap {
+    distances: HashMap<Pos, u32>,
+}
+
+impl DijkstraMap {
+    pub fn new<G, S, I>(goals: G, successors: S) -> Self
+    where
+        G: IntoIterator<Item = Pos>,
+        S: FnMut(Pos) -> I,
+        I: IntoIterator<Item = (Pos, u32)>,
+    {
+        Self::weighted(goals.into_iter().map(|goal| (goal, 0)), successors)
+    }
+
+    /// Like `new`, but each goal starts at its own cost.
+    pub fn weighted<G, S, I>(goals: G, mut successors: S) -> Self
+    where
+        G: IntoIterator<Item = (Pos, u32)>,
+        S: FnMut(Pos) -> I,
+        I: IntoIterator<Item = (Pos, u32)>,
+    {
+        let mut distances = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        for (goal, cost) in goals {
+            if distances.get(&goal).is_none_or(|&d| cost < d) {
+                distances.insert(goal, cost);
+                queue.push(Reverse((cost, goal.0, goal.1)));
+            }
+        }
+        while let Some(Reverse((cost, x, y))) = queue.pop() {
+            let pos = Pos(x, y);
+            if distances.get(&pos).is_some_and(|&d| d < cost) {
+                continue;
+            }
+            for (next, step) in successors(pos) {
+                let next_cost = cost + step;
+                if distances.get(&next).is_none_or(|&d| next_cost < d) {
+                    distances.insert(next, next_cost);
+                    queue.push(Reverse((next_cost, next.0, next.1)));
+                }
+            }
+        }
+        Self { distances }
+    }
+
+    /// Map leading away from this one's goals, favouring open routes over dead ends.
+    pub fn flee<S, I>(&self, successors: S) -> Self
+    where
+        S: FnMut(Pos) -> I,
+        I: IntoIterator<Item = (Pos, u32)>,
+    {
+        let max = self.distances.values().max().copied().unwrap_or(0);
+        let goals = self
+            .distances
+            .iter()
+            .map(|(&pos, &d)| (pos, (max - d) * FLEE_WEIGHT / 10));
+        Self::weighted(goals, successors)
+    }
+
+    /// True when there were no goals to measure from.
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    pub fn get(&self, pos: &Pos) -> Option<u32> {
+        self.distances.get(pos).copied()
+    }
+
+    pub fn downhill(&self, from: Pos, candidates: impl IntoIterator<Item = Pos>) -> Option<Pos> {
+        let current = self.get(&from)?;
+        candidates
+            .into_iter()
+            .filter_map(|p| self.get(&p).map(|d| (d, p)))
+            .filter(|(d, _)| *d < current)
+            .min_by_key(|(d, _)| *d)
+            .map(|(_, p)| p)
+    }
+}
@@ -1,22 +1,28 @@
-use crate::utils::distance;
-use crate::utils::Pos;
+use crate::utils::{Door, Pos};
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use raylib::{
-    prelude::{Color, KeyboardKey, RaylibDraw, RaylibDrawHandle, Rectangle},
-    RaylibHandle, RaylibThread,
-};
+use raylib::prelude::{Color, RaylibDraw, RaylibDrawHandle, Rectangle, Vector2};
 
 use crate::{
+    bindings::{Bindings, GameAction},
+    effects::{Anchor, Effects},
+    enemy::AiState,
+    gamepad::Gamepad,
+    look::describe,
+    motion::Motion,
     player,
+    screens::Screen,
+    settings::Settings,
     state::{EventType, State},
     Block, GameComponents,
 };
 
+const BOSS_BAR_WIDTH: i32 = 200;
+
 macro_rules! translate_pos {
-    ($pos:expr, $player:expr, $midpoint:expr, $vfactor:expr) => {{
+    ($pos:expr, $camera:expr, $midpoint:expr, $zoom:expr) => {{
         let (x, y) = $pos;
-        let x = (x * $vfactor) as i32 + $midpoint.x as i32 - $player.0 as i32 * $vfactor as i32;
-        let y = (y * $vfactor) as i32 + $midpoint.y as i32 - $player.1 as i32 * $vfactor as i32;
+        let x = ((x as f32 - $camera.x) * $zoom + $midpoint.x) as i32;
+        let y = ((y as f32 - $camera.y) * $zoom + $midpoint.y) as i32;
         (x, y)
     }};
 }
@@ -29,43 +35,55 @@ fn inside(pos: Rectangle, size: Rectangle) -> bool {
         && pos.y + pos.height < size.y + size.height
 }
 
-pub fn draw_end_screen(
-    rl: &mut RaylibHandle,
-    thread: &RaylibThread,
-    state: &mut State,
-    components: &GameComponents,
+pub fn draw_screen(
+    d: &mut RaylibDrawHandle,
+    screen: &Screen,
+    settings: &Settings,
+    bindings: &Bindings,
+    can_continue: bool,
+    size: &Rectangle,
 ) {
-    if let Some(KeyboardKey::KEY_R) = rl.get_key_pressed() {
-        state.player.xp = 0;
-        state.player.hp = 100;
-        state.log.clear();
-        //state.reset();
-        let dungeon = state.dungeon.clone().unwrap();
-        state.player.pos = (
-            dungeon.player_position.x as isize,
-            dungeon.player_position.y as isize,
-        )
-            .into()
-    }
-    // TODO: REVISAR COMO CENTRAR TEXTO
-    let k = rl.measure_text("DEAD", 20);
-    let mut d = rl.begin_drawing(thread);
     d.clear_background(Color::BLACK);
+    let heading = screen.heading();
+    let color = match screen {
+        Screen::Dead(_) => Color::RED,
+        Screen::Victory(_) => Color::GOLD,
+        _ => Color::RAYWHITE,
+    };
+    let center = (size.x + size.width / 2.0) as i32;
+    let mut y = (size.y + size.height / 4.0) as i32;
     d.draw_text(
-        "DEAD",
-        components.midpoint.x as i32 - 10,
-        components.midpoint.y as i32 - k / 2,
+        heading,
+        center - d.measure_text(heading, 40) / 2,
+        y,
         40,
-        Color::RED,
+        color,
     );
+    y += 80;
+    for (i, (line, enabled)) in screen
+        .lines(settings, bindings, can_continue)
+        .iter()
+        .enumerate()
+    {
+        let (text, color) = match screen.selected() {
+            Some(selected) if selected == i => (format!("> {} <", line), Color::YELLOW),
+            _ if *enabled => (line.clone(), Color::RAYWHITE),
+            _ => (line.clone(), Color::GRAY),
+        };
+        d.draw_text(&text, center - d.measure_text(&text, 20) / 2, y, 20, color);
+        y += 30;
+    }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn draw_main_screen(
     d: &mut RaylibDrawHandle,
     state: &mut State,
     enemies: &[Pos],
     items: &[Pos],
     components: &GameComponents,
+    effects: &Effects,
+    motion: &Motion,
     size: Rectangle,
 ) {
     d.clear_background(if !components.debug {
@@ -78,34 +96,31 @@ pub fn draw_main_screen(
     } else {
         state.map.keys().cloned().collect()
     };
-    for pos in vis {
+    for &pos in &vis {
         let (x, y) = translate_pos!(
             pos.as_tuple(),
-            state.player.pos,
+            components.camera.center,
             components.midpoint,
-            components.vfactor
+            components.camera.zoom
         );
         let dest_rect = Rectangle::new(
             x as f32,
             y as f32,
-            components.vfactor as f32,
-            components.vfactor as f32,
+            components.camera.zoom,
+            components.camera.zoom,
         );
         if !inside(dest_rect, size) {
             continue;
         }
-        let hue = match state.player.state {
-            player::PlayerState::Walking => Color::RED,
-            player::PlayerState::Combat(_) => Color::VIOLET,
-        };
+        let hue = state.light.tint(&pos).unwrap_or(Color::DARKGRAY);
 
         match state.map.get(&pos).or(state.teleporters_map.get(&pos)) {
             None => {
                 let dest_rect = Rectangle::new(
                     x as f32,
                     y as f32,
-                    components.vfactor as f32,
-                    components.vfactor as f32,
+                    components.camera.zoom,
+                    components.camera.zoom,
                 );
                 let mut r = StdRng::seed_from_u64((pos.0 * pos.1) as u64);
                 let k = r.gen_range(0..3);
@@ -117,23 +132,51 @@ pub fn draw_main_screen(
                     components.rotation,
                     hue,
                 );
+                if let Some(terrain) = state.terrain.get(&pos) {
+                    let zoom = components.camera.zoom as i32;
+                    d.draw_rectangle(x, y, zoom, zoom, terrain.color().fade(0.7));
+                }
             }
             Some(Block::Wall) => {
                 let dest_rect = Rectangle::new(
                     x as f32,
                     y as f32,
-                    components.vfactor as f32,
-                    components.vfactor as f32,
+                    components.camera.zoom,
+                    components.camera.zoom,
                 );
-                let upper_block = state.map.get(&(pos + (0, -1).into()));
-                let bottom_block = state.map.get(&(pos + (0, 1).into()));
-                let left_block = state.map.get(&(pos + (-1, 0).into()));
-                let right_block = state.map.get(&(pos + (1, 0).into()));
+                let upper_block = state
+                    .map
+                    .get(&(pos + (0, -1).into()))
+                    .filter(|b| **b == Block::Wall);
+                let bottom_block = state
+                    .map
+                    .get(&(pos + (0, 1).into()))
+                    .filter(|b| **b == Block::Wall);
+                let left_block = state
+                    .map
+                    .get(&(pos + (-1, 0).into()))
+                    .filter(|b| **b == Block::Wall);
+                let right_block = state
+                    .map
+                    .get(&(pos + (1, 0).into()))
+                    .filter(|b| **b == Block::Wall);
 
-                let right_upper_corner = state.map.get(&(pos + (1, -1).into()));
-                let right_bottom_corner = state.map.get(&(pos + (1, 1).into()));
-                let left_bottom_corner = state.map.get(&(pos + (-1, 1).into()));
-                let left_upper_corner = state.map.get(&(pos + (-1, -1).into()));
+                let right_upper_corner = state
+                    .map
+                    .get(&(pos + (1, -1).into()))
+                    .filter(|b| **b == Block::Wall);
+                let right_bottom_corner = state
+                    .map
+                    .get(&(pos + (1, 1).into()))
+                    .filter(|b| **b == Block::Wall);
+                let left_bottom_corner = state
+                    .map
+                    .get(&(pos + (-1, 1).into()))
+                    .filter(|b| **b == Block::Wall);
+                let left_upper_corner = state
+                    .map
+                    .get(&(pos + (-1, -1).into()))
+                    .filter(|b| **b == Block::Wall);
 
                 let rec = match (upper_block, right_block, bottom_block, left_block) {
                     // Horizontal and vertical texture computing based on empty adjacent tile
@@ -200,12 +243,16 @@ pub fn draw_main_screen(
             }
             Some(Block::Teleporter(_p)) => {
                 let rec = components.portal_rect;
+                let ready = state
+                    .pads
+                    .get(&pos)
+                    .is_none_or(|pad| pad.ready(state.stats.turns));
 
                 let dest_rect = Rectangle::new(
                     x as f32,
                     y as f32,
-                    components.vfactor as f32,
-                    components.vfactor as f32,
+                    components.camera.zoom,
+                    components.camera.zoom,
                 );
                 d.draw_texture_pro(
                     components.tex,
@@ -213,33 +260,92 @@ pub fn draw_main_screen(
                     dest_rect,
                     components.origin,
                     components.rotation,
+                    if ready { hue } else { hue.fade(0.4) },
+                );
+            }
+            Some(Block::Door(door)) => {
+                let dest_rect = Rectangle::new(
+                    x as f32,
+                    y as f32,
+                    components.camera.zoom,
+                    components.camera.zoom,
+                );
+                d.draw_texture_pro(
+                    components.tex,
+                    components.floor_rects[0],
+                    dest_rect,
+                    components.origin,
+                    components.rotation,
                     hue,
                 );
+                let zoom = components.camera.zoom as i32;
+                match door {
+                    Door::Open => d.draw_rectangle_lines(x, y, zoom, zoom, Color::BROWN),
+                    Door::Closed => d.draw_rectangle(x, y, zoom, zoom, Color::BROWN),
+                    Door::Locked(_) => {
+                        d.draw_rectangle(x, y, zoom, zoom, Color::BROWN);
+                        d.draw_rectangle(
+                            x + zoom / 3,
+                            y + zoom / 3,
+                            zoom / 3,
+                            zoom / 3,
+                            Color::GOLD,
+                        );
+                    }
+                }
             }
             Some(Block::Exit) => {
                 d.draw_rectangle(
                     x,
                     y,
-                    components.vfactor as i32,
-                    components.vfactor as i32,
+                    components.camera.zoom as i32,
+                    components.camera.zoom as i32,
                     Color::GREEN,
                 );
             }
         }
     }
+    for (pos, trap) in &state.traps {
+        let shown = trap.revealed && vis.contains(pos);
+        if !components.debug && !shown {
+            continue;
+        }
+        let (x, y) = translate_pos!(
+            pos.as_tuple(),
+            components.camera.center,
+            components.midpoint,
+            components.camera.zoom
+        );
+        let zoom = components.camera.zoom as i32;
+        d.draw_text("^", x + zoom / 3, y + zoom / 4, zoom / 2, trap.kind.color());
+    }
+    for pos in &state.braziers {
+        if !components.debug && !vis.contains(pos) {
+            continue;
+        }
+        let (x, y) = translate_pos!(
+            pos.as_tuple(),
+            components.camera.center,
+            components.midpoint,
+            components.camera.zoom
+        );
+        let half = components.camera.zoom / 2.0;
+        d.draw_circle(x + half as i32, y + half as i32, half / 2.0, Color::ORANGE);
+    }
     for enemy in enemies {
         let enemy = state.enemies.get(enemy).expect("enemy to be in the list");
+        let visual = motion.enemy(enemy.pos);
         let (x, y) = translate_pos!(
-            enemy.pos.as_tuple(),
-            state.player.pos,
+            (visual.x, visual.y),
+            components.camera.center,
             components.midpoint,
-            components.vfactor
+            components.camera.zoom
         );
         let dest_rect = Rectangle::new(
             x as f32,
             y as f32,
-            components.vfactor as f32,
-            components.vfactor as f32,
+            components.camera.zoom,
+            components.camera.zoom,
         );
         if !inside(dest_rect, size) {
             continue;
@@ -247,10 +353,41 @@ pub fn draw_main_screen(
         let dest_rect = Rectangle::new(
             x as f32,
             y as f32,
-            components.vfactor as f32,
-            components.vfactor as f32,
+            components.camera.zoom,
+            components.camera.zoom,
         );
 
+        if let player::PlayerState::Combat(targets) = &state.player.state {
+            if targets[components.target % targets.len()] == enemy.pos {
+                d.draw_rectangle_lines(
+                    x,
+                    y,
+                    components.camera.zoom as i32,
+                    components.camera.zoom as i32,
+                    Color::YELLOW,
+                );
+            }
+        }
+        if components.debug {
+            let walker = enemy.archetype.walker();
+            let blocked = |p: Pos| !state.in_bounds(&p) || state.enemies.contains_key(&p);
+            for (to, _) in walker.successors(
+                enemy.pos,
+                &state.map,
+                &state.traps,
+                &state.terrain,
+                &blocked,
+            ) {
+                let (px, py) = translate_pos!(
+                    to.as_tuple(),
+                    components.camera.center,
+                    components.midpoint,
+                    components.camera.zoom
+                );
+                let zoom = components.camera.zoom as i32;
+                d.draw_rectangle_lines(px + 2, py + 2, zoom - 4, zoom - 4, Color::MAGENTA);
+            }
+        }
         let size = ((enemy.hp * 100 / enemy.max_hp) * 30) / 100;
         d.draw_rectangle(x, y - 10, 30, 5, Color::GRAY);
         d.draw_rectangle(x, y - 10, size, 5, Color::RED);
@@ -260,28 +397,46 @@ pub fn draw_main_screen(
             dest_rect,
             components.origin,
             components.rotation,
-            Color::WHITE,
+            effects.tint(Anchor::Tile(enemy.pos)),
         );
     }
     for pos in items {
         let _item = state.items.get(pos).unwrap();
         let (x, y) = translate_pos!(
             pos.as_tuple(),
-            state.player.pos,
+            components.camera.center,
             components.midpoint,
-            components.vfactor
+            components.camera.zoom
         );
         d.draw_circle(x, y, 5.0, Color::VIOLET);
     }
+    if let Some(selected) = components.picking_teleporter {
+        for (i, pos) in state.known_teleporters().iter().enumerate() {
+            let (x, y) = translate_pos!(
+                pos.as_tuple(),
+                components.camera.center,
+                components.midpoint,
+                components.camera.zoom
+            );
+            let zoom = components.camera.zoom as i32;
+            let color = if i == selected {
+                d.draw_rectangle_lines(x, y, zoom, zoom, Color::YELLOW);
+                Color::YELLOW
+            } else {
+                Color::GRAY
+            };
+            d.draw_text(&(i + 1).to_string(), x, y, zoom / 2, color);
+        }
+    }
     for step in &state.path {
         let (mut x, mut y) = translate_pos!(
             step.as_tuple(),
-            state.player.pos,
+            components.camera.center,
             components.midpoint,
-            components.vfactor
+            components.camera.zoom
         );
-        x += (components.vfactor / 2) as i32;
-        y += (components.vfactor / 2) as i32;
+        x += (components.camera.zoom / 2.0) as i32;
+        y += (components.camera.zoom / 2.0) as i32;
 
         if !inside(Rectangle::new(x as f32, y as f32, 1.0, 1.0), size) {
             continue;
@@ -289,54 +444,128 @@ pub fn draw_main_screen(
         d.draw_circle(x, y, 1.0, Color::RAYWHITE);
     }
 
-    let dest_rect = Rectangle::new(
-        components.midpoint.x,
-        components.midpoint.y,
-        components.vfactor as f32,
-        components.vfactor as f32,
+    let visual = motion.player(state.player.pos);
+    let (x, y) = translate_pos!(
+        (visual.x, visual.y),
+        components.camera.center,
+        components.midpoint,
+        components.camera.zoom
     );
-    let size = ((state.player.hp * 100 / state.player.max_hp) * 30) / 100;
-    d.draw_rectangle(
-        components.midpoint.x as i32,
-        components.midpoint.y as i32 - 10,
-        30,
-        5,
-        Color::GRAY,
-    );
-    d.draw_rectangle(
-        components.midpoint.x as i32,
-        components.midpoint.y as i32 - 10,
-        size,
-        5,
-        Color::RED,
+    let dest_rect = Rectangle::new(
+        x as f32,
+        y as f32,
+        components.camera.zoom,
+        components.camera.zoom,
     );
+    let hp_bar = ((state.player.hp * 100 / state.player.max_hp) * 30) / 100;
+    d.draw_rectangle(x, y - 10, 30, 5, Color::GRAY);
+    d.draw_rectangle(x, y - 10, hp_bar, 5, Color::RED);
     d.draw_texture_pro(
         components.tex,
         components.player_rect,
         dest_rect,
         components.origin,
         state.player.get_swing_deg(),
-        Color::WHITE,
+        effects.tint(Anchor::Player),
     );
+    let half = components.camera.zoom / 2.0;
+    for (pos, progress) in effects.puffs() {
+        let (x, y) = translate_pos!(
+            pos.as_tuple(),
+            components.camera.center,
+            components.midpoint,
+            components.camera.zoom
+        );
+        let (x, y) = (x + half as i32, y + half as i32);
+        let radius = half * (0.5 + progress);
+        d.draw_circle(x, y, radius, Color::LIGHTGRAY.fade(0.6 * (1.0 - progress)));
+        d.draw_circle_lines(x, y, radius, Color::RAYWHITE.fade(1.0 - progress));
+    }
+    for (text, progress) in effects.texts() {
+        let pos = match text.anchor {
+            Anchor::Player => visual,
+            Anchor::Tile(pos) => Vector2::new(pos.0 as f32, pos.1 as f32),
+        };
+        let (x, y) = translate_pos!(
+            (pos.x, pos.y),
+            components.camera.center,
+            components.midpoint,
+            components.camera.zoom
+        );
+        let rise = (progress * components.camera.zoom) as i32;
+        d.draw_text(
+            &text.text,
+            x,
+            y - 20 - rise,
+            20,
+            text.color.fade(1.0 - progress),
+        );
+    }
+    if let Some(cursor) = components.look {
+        let (x, y) = translate_pos!(
+            cursor.as_tuple(),
+            components.camera.center,
+            components.midpoint,
+            components.camera.zoom
+        );
+        let zoom = components.camera.zoom as i32;
+        d.draw_rectangle_lines(x, y, zoom, zoom, Color::SKYBLUE);
+        let visible = components.debug || vis.contains(&cursor);
+        draw_tooltip(d, &describe(state, cursor, visible), x + zoom + 4, y, size);
+    }
+}
+
+fn draw_tooltip(d: &mut RaylibDrawHandle, lines: &[String], x: i32, y: i32, size: Rectangle) {
+    let width = lines
+        .iter()
+        .map(|line| d.measure_text(line, 20))
+        .max()
+        .unwrap_or(0)
+        + 16;
+    let height = lines.len() as i32 * 22 + 12;
+    let x = x
+        .min((size.x + size.width) as i32 - width)
+        .max(size.x as i32);
+    let y = y
+        .min((size.y + size.height) as i32 - height)
+        .max(size.y as i32);
+    d.draw_rectangle(x, y, width, height, Color::BLACK.fade(0.85));
+    d.draw_rectangle_lines(x, y, width, height, Color::SKYBLUE);
+    for (i, line) in lines.iter().enumerate() {
+        d.draw_text(line, x + 8, y + 6 + 22 * i as i32, 20, Color::RAYWHITE);
+    }
 }
 
-pub fn draw_ui(d: &mut RaylibDrawHandle, state: &State, size: &Rectangle) {
+pub fn draw_ui(
+    d: &mut RaylibDrawHandle,
+    state: &State,
+    bindings: &Bindings,
+    gamepad: &Gamepad,
+    size: &Rectangle,
+) {
     let banner = match &state.player.state {
         player::PlayerState::Walking => {
             format!(
                 "
 Walking (Hp: {}, XP: {})
 
+Depth: {} (seed {})
 Carrying: {}
 Markers: {}
 Exit distance: {}
 
+({}) Help
 ",
                 &state.player.hp,
                 &state.player.xp,
+                &state.depth,
+                &state.seed,
                 &state.player.carrying.name(),
                 &state.path.len(),
-                distance(state.player.pos, state.exit)
+                state
+                    .exit_distance
+                    .map_or("unknown".to_string(), |d| d.to_string()),
+                bindings.label(GameAction::Help)
             )
         }
         player::PlayerState::Combat(_) => {
@@ -344,18 +573,26 @@ Exit distance: {}
                 "
 In Combat (Hp: {0})
 
-(p) - Attack with {1}
-(o) - Use {1}
+({2}) - Attack with {1}
+({3}) - Use {1}
 
 ",
                 &state.player.hp,
-                &state.player.carrying.name()
+                &state.player.carrying.name(),
+                bindings.label(GameAction::Attack),
+                bindings.label(GameAction::Use)
             ) + &state
                 .player
                 .items
                 .iter()
                 .enumerate()
-                .map(|(i, v)| format!("({}) - Equip {}", i + 1, v.name()))
+                .map(|(i, v)| {
+                    format!(
+                        "({}) - Equip {}",
+                        bindings.label(GameAction::Equip(i)),
+                        v.name()
+                    )
+                })
                 .collect::<Vec<String>>()
                 .join("\n")
         }
@@ -364,15 +601,39 @@ In Combat (Hp: {0})
         let height = (size.y as i32) + (20 * i) as i32;
         d.draw_text(line, size.x as i32, height, 20, Color::RAYWHITE);
     }
+    if let Some(name) = &gamepad.name {
+        let height = (size.y as i32) + (20 * banner.lines().count()) as i32;
+        d.draw_text(name, size.x as i32, height, 20, Color::GRAY);
+    }
+    if let Some(boss) = state.boss().filter(|b| b.ai != AiState::Guarding) {
+        let x = size.x as i32;
+        let y = (size.y as i32) + (20 * (banner.lines().count() + 2)) as i32;
+        d.draw_text(boss.name(), x, y, 20, Color::ORANGE);
+        let fill = BOSS_BAR_WIDTH * boss.hp.max(0) / boss.max_hp;
+        d.draw_rectangle(x, y + 24, BOSS_BAR_WIDTH, 12, Color::GRAY);
+        d.draw_rectangle(x, y + 24, fill, 12, Color::RED);
+    }
 }
 pub fn draw_log(d: &mut RaylibDrawHandle, state: &State, size: &Rectangle) {
-    for (i, (line, event)) in state.log.iter().enumerate() {
+    for (i, entry) in state.log.recent().take(8).enumerate() {
         let height = (size.y as i32) + (20 * i) as i32;
-        let color = match event {
+        let color = match entry.etype {
             EventType::DamageDealt => Color::DARKRED,
             EventType::DamageTaken => Color::RED,
             EventType::Teleport | EventType::XP => Color::GREEN,
+            EventType::Item => Color::SKYBLUE,
+            EventType::Info => Color::RAYWHITE,
         };
-        d.draw_text(line, size.x as i32, height, 20, color);
+        d.draw_text(&entry.text(), size.x as i32, height, 20, color);
+    }
+}
+pub fn draw_help(d: &mut RaylibDrawHandle, bindings: &Bindings, size: &Rectangle) {
+    d.draw_rectangle_rec(size, Color::BLACK.fade(0.85));
+    let lines = bindings.help();
+    let rows = lines.len().div_ceil(2);
+    for (i, line) in lines.iter().enumerate() {
+        let x = size.x as i32 + 20 + (i / rows) as i32 * (size.width as i32 / 2);
+        let y = size.y as i32 + 20 + (20 * (i % rows)) as i32;
+        d.draw_text(line, x, y, 20, Color::RAYWHITE);
     }
 }
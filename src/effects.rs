@@ -0,0 +1,106 @@
+use raylib::prelude::Color;
+
+use crate::{
+    events::{Actor, GameEvent, Subscriber},
+    utils::Pos,
+};
+
+const TEXT_SECONDS: f64 = 1.0;
+const FLASH_SECONDS: f64 = 0.15;
+const PUFF_SECONDS: f64 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Anchor {
+    Player,
+    Tile(Pos),
+}
+
+#[derive(Debug, Clone)]
+pub struct FloatingText {
+    pub anchor: Anchor,
+    pub text: String,
+    pub color: Color,
+    born: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Effects {
+    now: f64,
+    texts: Vec<FloatingText>,
+    flashes: Vec<(Anchor, f64)>,
+    puffs: Vec<(Pos, f64)>,
+}
+
+impl Effects {
+    pub fn update(&mut self, now: f64) {
+        self.now = now;
+        self.texts.retain(|t| now - t.born < TEXT_SECONDS);
+        self.flashes.retain(|(_, born)| now - born < FLASH_SECONDS);
+        self.puffs.retain(|(_, born)| now - born < PUFF_SECONDS);
+    }
+
+    fn progress(&self, born: f64, seconds: f64) -> f32 {
+        ((self.now - born) / seconds).clamp(0.0, 1.0) as f32
+    }
+
+    fn float(&mut self, anchor: Anchor, text: String, color: Color) {
+        self.texts.push(FloatingText {
+            anchor,
+            text,
+            color,
+            born: self.now,
+        });
+    }
+
+    pub fn tint(&self, anchor: Anchor) -> Color {
+        if self.flashes.iter().any(|(a, _)| *a == anchor) {
+            Color::RED
+        } else {
+            Color::WHITE
+        }
+    }
+
+    pub fn texts(&self) -> impl Iterator<Item = (&FloatingText, f32)> {
+        self.texts
+            .iter()
+            .map(|t| (t, self.progress(t.born, TEXT_SECONDS)))
+    }
+
+    pub fn puffs(&self) -> impl Iterator<Item = (Pos, f32)> + '_ {
+        self.puffs
+            .iter()
+            .map(|&(pos, born)| (pos, self.progress(born, PUFF_SECONDS)))
+    }
+}
+
+impl Subscriber for Effects {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Attack {
+                target,
+                amount,
+                pos,
+                ..
+            } => {
+                let anchor = match target {
+                    Actor::Player => Anchor::Player,
+                    Actor::Enemy(_) | Actor::Environment(_) => Anchor::Tile(*pos),
+                };
+                if *amount > 0 {
+                    self.float(anchor, format!("-{}", amount), Color::RED);
+                    self.flashes.push((anchor, self.now));
+                } else {
+                    self.float(anchor, "miss".to_string(), Color::LIGHTGRAY);
+                }
+            }
+            GameEvent::Heal { amount } => {
+                self.float(Anchor::Player, format!("+{}", amount), Color::GREEN);
+            }
+            GameEvent::EnemyDied { pos, xp, .. } => {
+                self.puffs.push((*pos, self.now));
+                self.float(Anchor::Tile(*pos), format!("+{} XP", xp), Color::GOLD);
+            }
+            _ => {}
+        }
+    }
+}
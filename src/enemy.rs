@@ -1,13 +1,216 @@
 use std::collections::HashMap;
 
 use crate::{
+    ability::{Ability, Status, Statuses, MAX_SUMMONS},
+    boss::{Boss, BossKind},
+    dijkstra::DijkstraMap,
+    events::Actor,
     item::{EditableEntity, Value},
     utils::Pos,
 };
 use pathfinding::prelude::astar;
-use rand::Rng;
+use rand::{
+    distributions::{Distribution, Standard},
+    Rng,
+};
+
+use crate::{
+    distance,
+    movement::{Blink, KingStep, KnightJump, RookSlide, Turret},
+    pathing::{Pathing, Walker},
+    player::Player,
+    terrain::Terrain,
+    traps::Trap,
+    utils::{open_door, Door},
+    Block,
+};
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Archetype {
+    Ghost,
+    Goblin,
+    Golem,
+    Imp,
+    Sentry,
+    Shaman,
+}
+
+impl Distribution<Archetype> for Standard {
+    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Archetype {
+        match rng.gen_range(0..11) {
+            0..=3 => Archetype::Ghost,
+            4..=6 => Archetype::Goblin,
+            7 => Archetype::Golem,
+            8 => Archetype::Imp,
+            9 => Archetype::Sentry,
+            10 => Archetype::Shaman,
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Behaviour {
+    pub sight: f32,
+    /// Multiplier on how far away a noise can be heard.
+    pub hearing: f32,
+    /// Percentage of max HP below which the enemy runs away, 0 to never flee.
+    pub flee_below: i32,
+    pub sleep_chance: f64,
+    pub search_turns: u32,
+    /// Distance a hunting enemy keeps from the player, 0 to close in.
+    pub range: f32,
+}
+
+impl Archetype {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Archetype::Ghost => "Ghost",
+            Archetype::Goblin => "Goblin",
+            Archetype::Golem => "Golem",
+            Archetype::Imp => "Imp",
+            Archetype::Sentry => "Sentry",
+            Archetype::Shaman => "Shaman",
+        }
+    }
+    pub fn opens_doors(&self) -> bool {
+        matches!(self, Archetype::Goblin | Archetype::Imp | Archetype::Shaman)
+    }
+    pub fn walker(&self) -> Walker {
+        Walker {
+            pattern: match self {
+                Archetype::Ghost => &KnightJump,
+                Archetype::Goblin => &KingStep,
+                Archetype::Golem => &RookSlide,
+                Archetype::Imp => &Blink,
+                Archetype::Sentry => &Turret,
+                Archetype::Shaman => &KingStep,
+            },
+            opens_doors: self.opens_doors(),
+        }
+    }
+    pub fn behaviour(&self) -> Behaviour {
+        match self {
+            Archetype::Ghost => Behaviour {
+                sight: 8.0,
+                hearing: 0.5,
+                flee_below: 0,
+                sleep_chance: 0.3,
+                search_turns: 12,
+                range: 0.0,
+            },
+            Archetype::Goblin => Behaviour {
+                sight: 6.0,
+                hearing: 1.5,
+                flee_below: 30,
+                sleep_chance: 0.5,
+                search_turns: 6,
+                range: 0.0,
+            },
+            Archetype::Golem => Behaviour {
+                sight: 5.0,
+                hearing: 1.0,
+                flee_below: 0,
+                sleep_chance: 0.6,
+                search_turns: 8,
+                range: 0.0,
+            },
+            Archetype::Imp => Behaviour {
+                sight: 7.0,
+                hearing: 1.0,
+                flee_below: 50,
+                sleep_chance: 0.2,
+                search_turns: 10,
+                range: 4.0,
+            },
+            Archetype::Sentry => Behaviour {
+                sight: 8.0,
+                hearing: 0.5,
+                flee_below: 0,
+                sleep_chance: 0.0,
+                search_turns: 4,
+                range: 0.0,
+            },
+            Archetype::Shaman => Behaviour {
+                sight: 7.0,
+                hearing: 1.0,
+                flee_below: 40,
+                sleep_chance: 0.4,
+                search_turns: 8,
+                range: 5.0,
+            },
+        }
+    }
+    pub fn abilities(&self) -> &'static [Ability] {
+        match self {
+            Archetype::Imp => &[Ability::Bolt {
+                damage: 6,
+                range: 6.0,
+                status: Some(Status::Burning),
+            }],
+            Archetype::Sentry => &[Ability::Bolt {
+                damage: 10,
+                range: 8.0,
+                status: None,
+            }],
+            Archetype::Shaman => &[
+                Ability::Mend {
+                    amount: 10,
+                    radius: 5.0,
+                },
+                Ability::Summon(Archetype::Goblin),
+            ],
+            Archetype::Ghost | Archetype::Goblin | Archetype::Golem => &[],
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AiState {
+    Asleep,
+    /// Holds its post until it notices the player.
+    Guarding,
+    Wandering,
+    Hunting {
+        last_seen: Pos,
+    },
+    Searching {
+        target: Pos,
+        turns: u32,
+    },
+    Fleeing,
+}
+
+impl AiState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AiState::Asleep => "asleep",
+            AiState::Guarding => "guarding",
+            AiState::Wandering => "wandering",
+            AiState::Hunting { .. } => "hunting",
+            AiState::Searching { .. } => "searching",
+            AiState::Fleeing => "fleeing",
+        }
+    }
+}
 
-use crate::{distance, player::Player, Block};
+/// What an enemy perceived this turn.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Senses {
+    pub sees_player: bool,
+    pub heard: Option<Pos>,
+    /// Far from the player, so paths are approximated instead of searched.
+    pub far: bool,
+    pub line_of_fire: bool,
+}
+
+/// What an enemy did to the player this turn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Act {
+    Melee(i32),
+    /// Left for `State::cast` to resolve once every enemy has moved.
+    Cast(Ability, Pos),
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Enemy {
@@ -17,6 +220,17 @@ pub struct Enemy {
     timer: i32,
     pub pos: Pos,
     pub dificulty: u32,
+    pub archetype: Archetype,
+    pub ai: AiState,
+    pub statuses: Statuses,
+    pub boss: Option<Boss>,
+    /// Whoever dealt the latest damage, credited with the kill.
+    pub last_attacker: Option<Actor>,
+    /// Set by `State::spawn`, never reused.
+    pub id: u32,
+    /// The `id` of the enemy that summoned this one.
+    pub summoner: Option<u32>,
+    cooldown: u32,
 }
 
 impl EditableEntity for Enemy {
@@ -30,8 +244,17 @@ impl EditableEntity for Enemy {
 }
 
 impl Enemy {
-    pub fn new(hp: i32, pos: Pos, dificulty: u32) -> Self {
-        let luck = rand::thread_rng().gen_range(0..5);
+    pub fn new(hp: i32, pos: Pos, dificulty: u32, rng: &mut impl Rng) -> Self {
+        let archetype: Archetype = rng.gen();
+        Self::of(archetype, hp, pos, dificulty, rng)
+    }
+    pub fn of(archetype: Archetype, hp: i32, pos: Pos, dificulty: u32, rng: &mut impl Rng) -> Self {
+        let luck = rng.gen_range(0..5);
+        let ai = if rng.gen_bool(archetype.behaviour().sleep_chance) {
+            AiState::Asleep
+        } else {
+            AiState::Wandering
+        };
         Self {
             hp,
             max_hp: hp,
@@ -39,58 +262,168 @@ impl Enemy {
             luck,
             timer: 0,
             dificulty,
+            archetype,
+            ai,
+            statuses: Statuses::default(),
+            boss: None,
+            last_attacker: None,
+            id: 0,
+            summoner: None,
+            cooldown: 0,
         }
     }
-    pub fn hit_by(&mut self, damage: i32) {
+    pub fn boss(kind: BossKind, pos: Pos, dificulty: u32, rng: &mut impl Rng) -> Self {
+        let mut enemy = Self::of(kind.archetype(), kind.hp(), pos, dificulty, rng);
+        enemy.boss = Some(Boss::new(kind));
+        enemy.ai = AiState::Guarding;
+        enemy
+    }
+    pub fn name(&self) -> &str {
+        self.boss
+            .map_or(self.archetype.name(), |boss| boss.kind.name())
+    }
+    pub fn abilities(&self) -> &'static [Ability] {
+        self.boss
+            .map_or(self.archetype.abilities(), |boss| boss.current().abilities)
+    }
+    pub fn hit_by(&mut self, damage: i32, attacker: &Actor) {
         self.hp -= damage;
+        if damage > 0 {
+            self.last_attacker = Some(attacker.clone());
+        }
     }
+    fn think(&mut self, senses: &Senses, player: Pos) {
+        let behaviour = self.archetype.behaviour();
+        let hurt = self.boss.is_none() && self.hp * 100 < self.max_hp * behaviour.flee_below;
+        let search = |target| AiState::Searching {
+            target,
+            turns: behaviour.search_turns,
+        };
+        self.ai = match self.ai {
+            _ if hurt => AiState::Fleeing,
+            // Patched up again, so it comes back looking for the player.
+            AiState::Fleeing => search(player),
+            AiState::Asleep => senses.heard.map_or(AiState::Asleep, search),
+            _ if senses.sees_player => AiState::Hunting { last_seen: player },
+            AiState::Hunting { last_seen } => search(last_seen),
+            _ if senses.heard.is_some() => search(senses.heard.unwrap()),
+            AiState::Searching { turns: 0, .. } => AiState::Wandering,
+            AiState::Searching { target, turns } => AiState::Searching {
+                target,
+                turns: turns - 1,
+            },
+            AiState::Guarding => AiState::Guarding,
+            AiState::Wandering => AiState::Wandering,
+        };
+    }
+    fn pick_ability(
+        &self,
+        target: Pos,
+        enemies: &HashMap<Pos, Self>,
+        senses: &Senses,
+    ) -> Option<Ability> {
+        if self.cooldown > 0 {
+            return None;
+        }
+        self.abilities()
+            .iter()
+            .find(|ability| match ability {
+                Ability::Bolt { range, .. } => {
+                    senses.line_of_fire && distance(self.pos, target) <= *range
+                }
+                Ability::Mend { radius, .. } => enemies.values().any(|e| {
+                    e.pos != self.pos && e.hp < e.max_hp && distance(e.pos, self.pos) <= *radius
+                }),
+                Ability::Summon(_) => {
+                    senses.sees_player
+                        && enemies
+                            .values()
+                            .filter(|e| e.summoner == Some(self.id))
+                            .count()
+                            < MAX_SUMMONS
+                }
+            })
+            .copied()
+    }
+    #[allow(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         player: &mut Player,
-        map: &HashMap<Pos, Block>,
+        map: &mut HashMap<Pos, Block>,
         enemies: &HashMap<Pos, Self>,
-        flag: bool,
-    ) -> Option<i32> {
-        if !flag {
+        traps: &HashMap<Pos, Trap>,
+        terrain: &HashMap<Pos, Terrain>,
+        pathing: &Pathing,
+        senses: Senses,
+        rng: &mut impl Rng,
+    ) -> Option<Act> {
+        if self.hp <= 0 {
             return None;
         }
         if self.timer < 1 {
             self.timer = 22;
         }
         self.timer -= 1;
-        if self.timer % 3 == 0 && distance(self.pos, player.pos) < 3.0 {
-            let damage = rand::thread_rng().gen_range(0..self.luck + 1);
-            player.hit_by(damage);
-            return Some(damage);
+        self.cooldown = self.cooldown.saturating_sub(1);
+        self.think(&senses, player.pos);
+        let gap = distance(self.pos, player.pos);
+        let goal = match self.ai {
+            AiState::Asleep | AiState::Guarding => return None,
+            AiState::Hunting { last_seen } => {
+                if self.timer % 3 == 0 {
+                    if let Some(ability) = self.pick_ability(player.pos, enemies, &senses) {
+                        self.cooldown = ability.cooldown();
+                        return Some(Act::Cast(ability, player.pos));
+                    }
+                    if gap < 3.0 {
+                        let damage = rng.gen_range(0..self.luck + 1);
+                        player.hit_by(damage);
+                        return Some(Act::Melee(damage));
+                    }
+                }
+                Some(last_seen)
+            }
+            AiState::Searching { target, .. } if distance(self.pos, target) >= 3.0 => Some(target),
+            AiState::Searching { .. } | AiState::Wandering | AiState::Fleeing => None,
+        };
+        if self.timer % 5 != 0 {
+            return None;
         }
-        let path = astar(
-            &self.pos.as_tuple(),
-            |&(x, y)| {
-                vec![
-                    (x + 1, y + 2),
-                    (x + 1, y - 2),
-                    (x - 1, y + 2),
-                    (x - 1, y - 2),
-                    (x + 2, y + 1),
-                    (x + 2, y - 1),
-                    (x - 2, y + 1),
-                    (x - 2, y - 1),
-                ]
+        let walker = self.archetype.walker();
+        let blocked = |p: Pos| !pathing.in_bounds(&p) || enemies.contains_key(&p);
+        let neighbours = |pos: Pos| walker.successors(pos, map, traps, terrain, &blocked);
+        let downhill = |map: Option<&DijkstraMap>| {
+            map?.downhill(self.pos, neighbours(self.pos).into_iter().map(|(p, _)| p))
+        };
+        let range = self.archetype.behaviour().range;
+        let keep_away = range > 0.0 && matches!(self.ai, AiState::Hunting { .. });
+        let next = match (self.ai, goal) {
+            _ if keep_away && gap < range => downhill(pathing.flee(&self.archetype)),
+            _ if keep_away && senses.line_of_fire => None,
+            (_, Some(goal)) if goal == player.pos => downhill(pathing.chase(&self.archetype)),
+            (_, Some(goal)) if senses.far => neighbours(self.pos)
                 .into_iter()
-                .filter(|x| {
-                    let x = Pos(x.0, x.1);
-                    !map.contains_key(&x) && !enemies.contains_key(&x) && self.timer % 5 == 0
-                })
-                .map(|p| (p, 1))
-            },
-            |&(x, y)| (player.pos.0.abs_diff(x) + player.pos.1.abs_diff(y)) / 2,
-            |&p| p == player.pos.as_tuple(),
-        );
-        if let Some((p, _)) = &path {
-            if let Some(x) = p.get(1) {
-                if *x != player.pos.as_tuple() {
-                    self.pos = (*x).into()
-                }
+                .map(|(p, _)| p)
+                .min_by(|a, b| distance(*a, goal).total_cmp(&distance(*b, goal)))
+                .filter(|p| distance(*p, goal) < distance(self.pos, goal)),
+            (_, Some(goal)) => astar(
+                &self.pos,
+                |&p| neighbours(p),
+                |&p| ((goal.0.abs_diff(p.0) + goal.1.abs_diff(p.1)) / 2) as u32,
+                |&p| p == goal,
+            )
+            .and_then(|(path, _)| path.get(1).copied()),
+            (AiState::Fleeing, None) => downhill(pathing.flee(&self.archetype)),
+            (_, None) => {
+                let options = neighbours(self.pos);
+                (!options.is_empty()).then(|| options[rng.gen_range(0..options.len())].0)
+            }
+        };
+        if let Some(next) = next {
+            if map.get(&next) == Some(&Block::Door(Door::Closed)) {
+                open_door(map, next);
+            } else if next != player.pos {
+                self.pos = next
             }
         }
         None
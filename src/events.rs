@@ -0,0 +1,183 @@
+use crate::{state::EventType, utils::Pos};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Actor {
+    Player,
+    Enemy(String),
+    /// Terrain, traps and statuses, named as the cause of the damage.
+    Environment(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GameEvent {
+    Attack {
+        attacker: Actor,
+        target: Actor,
+        amount: i32,
+        pos: Pos,
+    },
+    Heal {
+        amount: i32,
+    },
+    EnemyDied {
+        name: String,
+        killer: Option<Actor>,
+        pos: Pos,
+        xp: i32,
+    },
+    PickUp {
+        item: String,
+        pos: Pos,
+    },
+    Equip {
+        item: String,
+    },
+    Teleport {
+        from: Pos,
+        to: Pos,
+    },
+    Descend {
+        depth: u32,
+    },
+    Move {
+        actor: Actor,
+        from: Pos,
+        to: Pos,
+    },
+    DoorOpened {
+        pos: Pos,
+        key: Option<String>,
+    },
+    TrapSprung {
+        trap: String,
+        actor: Actor,
+        pos: Pos,
+    },
+    TrapFound {
+        trap: String,
+        pos: Pos,
+    },
+    TrapDisarmed {
+        trap: String,
+        pos: Pos,
+    },
+    Info(String),
+}
+
+impl GameEvent {
+    pub fn etype(&self) -> EventType {
+        match self {
+            GameEvent::Attack {
+                attacker: Actor::Player,
+                ..
+            } => EventType::DamageDealt,
+            GameEvent::Attack { .. } => EventType::DamageTaken,
+            GameEvent::EnemyDied { .. } => EventType::XP,
+            GameEvent::PickUp { .. } | GameEvent::Equip { .. } | GameEvent::Heal { .. } => {
+                EventType::Item
+            }
+            GameEvent::Teleport { .. } | GameEvent::Descend { .. } => EventType::Teleport,
+            GameEvent::Move { .. }
+            | GameEvent::DoorOpened { .. }
+            | GameEvent::TrapSprung { .. }
+            | GameEvent::TrapFound { .. }
+            | GameEvent::TrapDisarmed { .. }
+            | GameEvent::Info(_) => EventType::Info,
+        }
+    }
+
+    pub fn message(&self) -> Option<String> {
+        let message = match self {
+            GameEvent::Attack {
+                attacker: Actor::Player,
+                target,
+                amount,
+                ..
+            } => format!("You attacked {} for {} damage", target.name(), amount),
+            GameEvent::Attack {
+                attacker: Actor::Environment(cause),
+                target: Actor::Player,
+                amount,
+                ..
+            } => format!("You take {} damage from {}", amount, cause),
+            GameEvent::Attack {
+                attacker: Actor::Environment(cause),
+                target,
+                amount,
+                ..
+            } => format!("{} takes {} damage from {}", target.name(), amount, cause),
+            GameEvent::Attack {
+                attacker,
+                target: Actor::Player,
+                amount,
+                ..
+            } => format!("{} hits you for {} damage", attacker.name(), amount),
+            GameEvent::Attack {
+                attacker,
+                target,
+                amount,
+                ..
+            } => format!(
+                "{} hits {} for {} damage",
+                attacker.name(),
+                target.name(),
+                amount
+            ),
+            GameEvent::Heal { amount } => format!("You recover {} HP", amount),
+            GameEvent::EnemyDied { name, xp, .. } => format!("{} died (+{} XP)", name, xp),
+            GameEvent::PickUp { item, .. } => format!("You picked up {}", item),
+            GameEvent::Equip { item } => format!("You equip {}", item),
+            GameEvent::Teleport { .. } => "Teleporter activated".to_string(),
+            GameEvent::Descend { depth } => format!("You descend to depth {}", depth),
+            GameEvent::Move { .. } => return None,
+            GameEvent::DoorOpened { key: None, .. } => "You open the door".to_string(),
+            GameEvent::DoorOpened { key: Some(key), .. } => {
+                format!("You unlock the door with the {}", key)
+            }
+            GameEvent::TrapSprung {
+                trap,
+                actor: Actor::Player,
+                ..
+            } => format!("You trigger a {}", trap),
+            GameEvent::TrapSprung { trap, actor, .. } => {
+                format!("{} triggers a {}", actor.name(), trap)
+            }
+            GameEvent::TrapFound { trap, .. } => format!("You find a {}", trap),
+            GameEvent::TrapDisarmed { trap, .. } => format!("You disarm the {}", trap),
+            GameEvent::Info(message) => message.clone(),
+        };
+        Some(message)
+    }
+}
+
+impl Actor {
+    pub fn name(&self) -> String {
+        match self {
+            Actor::Player => "you".to_string(),
+            Actor::Enemy(name) | Actor::Environment(name) => name.clone(),
+        }
+    }
+}
+
+pub trait Subscriber {
+    fn notify(&mut self, event: &GameEvent);
+}
+
+#[derive(Debug, Default)]
+pub struct EventBus {
+    queue: Vec<GameEvent>,
+}
+
+impl EventBus {
+    pub fn publish(&mut self, event: GameEvent) {
+        self.queue.push(event);
+    }
+
+    pub fn dispatch(&mut self, subscribers: &mut [&mut dyn Subscriber]) {
+        for event in self.queue.drain(..) {
+            for subscriber in subscribers.iter_mut() {
+                subscriber.notify(&event);
+            }
+        }
+    }
+}
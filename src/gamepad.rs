@@ -0,0 +1,54 @@
+use raylib::{ffi::GamepadAxis, RaylibHandle};
+
+use crate::bindings::{Bindings, GameAction};
+
+const MAX_GAMEPADS: i32 = 4;
+const STICK_DEADZONE: f32 = 0.5;
+
+#[derive(Debug, Default)]
+pub struct Gamepad {
+    pub id: Option<i32>,
+    pub name: Option<String>,
+}
+
+impl Gamepad {
+    pub fn poll(&mut self, rl: &RaylibHandle) -> Option<String> {
+        if let Some(id) = self.id {
+            if rl.is_gamepad_available(id) {
+                return None;
+            }
+            self.id = None;
+            let name = self.name.take().unwrap_or_default();
+            return Some(format!("Gamepad disconnected: {}", name));
+        }
+        let id = (0..MAX_GAMEPADS).find(|&id| rl.is_gamepad_available(id))?;
+        let name = rl
+            .get_gamepad_name(id)
+            .unwrap_or_else(|| format!("Gamepad {}", id));
+        self.id = Some(id);
+        self.name = Some(name.clone());
+        Some(format!("Gamepad connected: {}", name))
+    }
+
+    pub fn action(&self, rl: &RaylibHandle, bindings: &Bindings) -> Option<GameAction> {
+        bindings.pad_action(rl, self.id?)
+    }
+
+    pub fn held(&self, rl: &RaylibHandle, bindings: &Bindings, action: GameAction) -> bool {
+        let Some(id) = self.id else {
+            return false;
+        };
+        let axis = |axis| rl.get_gamepad_axis_movement(id, axis);
+        let stick = match action {
+            GameAction::MoveUp => axis(GamepadAxis::GAMEPAD_AXIS_LEFT_Y) < -STICK_DEADZONE,
+            GameAction::MoveDown => axis(GamepadAxis::GAMEPAD_AXIS_LEFT_Y) > STICK_DEADZONE,
+            GameAction::MoveLeft => axis(GamepadAxis::GAMEPAD_AXIS_LEFT_X) < -STICK_DEADZONE,
+            GameAction::MoveRight => axis(GamepadAxis::GAMEPAD_AXIS_LEFT_X) > STICK_DEADZONE,
+            _ => false,
+        };
+        stick
+            || bindings
+                .buttons(action)
+                .any(|button| rl.is_gamepad_button_down(id, button))
+    }
+}
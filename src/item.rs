@@ -4,6 +4,8 @@ use rand::{
 };
 use std::collections::HashMap;
 
+use crate::ability::{Ability, Status, BOLT_RANGE};
+
 pub trait EditableEntity {
     fn heal(&mut self, value: &Value);
     fn damage(&mut self, value: &Value);
@@ -14,18 +16,26 @@ pub enum ItemType {
     Melee,
     Ingredient,
     HealPotion,
+    Key(u32),
+    /// Shoots bolts, optionally leaving a status on whoever they hit.
+    Wand(Option<Status>),
 }
 impl Distribution<ItemType> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ItemType {
-        match rng.gen_range(0..3) {
+        match rng.gen_range(0..4) {
             0 => ItemType::Melee,
             1 => ItemType::Ingredient,
             2 => ItemType::HealPotion,
+            3 => ItemType::Wand(
+                [None, Some(Status::Burning), Some(Status::Poisoned)][rng.gen_range(0..3)],
+            ),
             _ => unreachable!(),
         }
     }
 }
 
+const KEY_NAMES: [&str; 4] = ["Brass Key", "Iron Key", "Silver Key", "Bone Key"];
+
 struct SwordName<'a>(&'a str);
 
 impl<'a> Distribution<SwordName<'a>> for Standard {
@@ -58,10 +68,10 @@ pub struct Item {
 
 impl Distribution<Item> for Standard {
     fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Item {
-        let ty: ItemType = rand::random();
+        let ty: ItemType = rng.gen();
         match ty {
             ItemType::Melee => {
-                let name: SwordName = rand::random();
+                let name: SwordName = rng.gen();
                 let actions = HashMap::from([(Action::Damage, rng.gen_range(0..30))]);
                 Item {
                     ty,
@@ -76,10 +86,24 @@ impl Distribution<Item> for Standard {
             },
             ItemType::HealPotion => {
                 let name = "Heal Potion".to_string();
-                let actions = HashMap::from([(Action::Damage, rng.gen_range(0..30))]);
+                let actions = HashMap::from([(Action::Heal, rng.gen_range(0..30))]);
 
                 Item { ty, name, actions }
             }
+            ItemType::Key(id) => Item::key(id),
+            ItemType::Wand(status) => {
+                let name = match status {
+                    None => "Wand Of Sparks",
+                    Some(Status::Burning) => "Fire Wand",
+                    Some(Status::Poisoned) => "Venom Wand",
+                };
+                let actions = HashMap::from([(Action::Damage, rng.gen_range(5..20))]);
+                Item {
+                    ty,
+                    name: name.to_string(),
+                    actions,
+                }
+            }
         }
     }
 }
@@ -88,9 +112,39 @@ impl Item {
     pub fn new(name: String, ty: ItemType, actions: HashMap<Action, Value>) -> Self {
         Self { name, ty, actions }
     }
+    pub fn fists() -> Self {
+        Self::new(
+            "Fists".into(),
+            ItemType::Melee,
+            HashMap::from([(Action::Damage, 5)]),
+        )
+    }
+    pub fn key(id: u32) -> Self {
+        Self::new(
+            KEY_NAMES[id as usize % KEY_NAMES.len()].into(),
+            ItemType::Key(id),
+            HashMap::new(),
+        )
+    }
     pub fn name(&self) -> &String {
         &self.name
     }
+    pub fn ty(&self) -> &ItemType {
+        &self.ty
+    }
+    pub fn value(&self, action: &Action) -> Value {
+        self.actions.get(action).copied().unwrap_or_default()
+    }
+    pub fn ability(&self) -> Option<Ability> {
+        let ItemType::Wand(status) = self.ty else {
+            return None;
+        };
+        Some(Ability::Bolt {
+            damage: self.value(&Action::Damage),
+            range: BOLT_RANGE,
+            status,
+        })
+    }
     pub fn apply(&self, user: &mut impl EditableEntity, target: &mut impl EditableEntity) {
         for (action, value) in &self.actions {
             match action {
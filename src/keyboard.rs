@@ -1,48 +1,132 @@
-use std::collections::HashMap;
-
+use bounded_vec_deque::BoundedVecDeque;
 use raylib::{ffi::KeyboardKey, RaylibHandle};
 
-pub fn debounce(
-    rl: &RaylibHandle,
-    key: KeyboardKey,
-    delay: &mut u32,
-    map: &mut HashMap<KeyboardKey, u32>,
-) -> bool {
-    if rl.is_key_down(key) {
-        if let Some(&mut ref mut nd) = map.get_mut(&key) {
-            if *nd < 1 {
-                map.remove(&key);
-                return true;
+use crate::{
+    bindings::{Bindings, GameAction},
+    gamepad::Gamepad,
+    utils::Pos,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Move(Pos),
+    Act(GameAction),
+}
+
+#[derive(Debug)]
+pub struct KeyRepeat {
+    pub delay_ms: u32,
+    pub rate_ms: u32,
+    held: Option<(Pos, f64)>,
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        Self {
+            delay_ms: 250,
+            rate_ms: 100,
+            held: None,
+        }
+    }
+}
+
+impl KeyRepeat {
+    pub fn update(&mut self, direction: Pos, now: f64) -> bool {
+        if direction == Pos(0, 0) {
+            self.held = None;
+            return false;
+        }
+        match self.held {
+            Some((held, next)) if held == direction => {
+                if now < next {
+                    return false;
+                }
+                self.held = Some((held, now + self.rate_ms as f64 / 1000.0));
+                true
+            }
+            // A new press already stepped, and releasing one key of a chord
+            // shouldn't step at all, so either way wait out the delay again.
+            _ => {
+                self.held = Some((direction, now + self.delay_ms as f64 / 1000.0));
+                false
             }
-            *nd -= 1;
-        } else {
-            map.insert(key, *delay);
         }
     }
-    false
 }
 
-#[macro_export]
-macro_rules! debounce_key_action {
-    ($key:expr => $rl:expr => $k:expr => $debounce_map:expr => $state:expr => $next:expr) => {
-        if $crate::keyboard::debounce(&$rl, $key, &mut $k, $debounce_map) {
-            $next($state)
+#[derive(Debug)]
+pub struct InputBuffer {
+    pub repeat: KeyRepeat,
+    queue: BoundedVecDeque<Command>,
+}
+
+impl Default for InputBuffer {
+    fn default() -> Self {
+        Self {
+            repeat: KeyRepeat::default(),
+            queue: BoundedVecDeque::new(4),
         }
-    };
+    }
 }
 
-#[macro_export]
-macro_rules! debounce_key_move {
-    ($key:expr => $delta:expr => $rl:expr => $k:expr => $debounce_map:expr => $state:expr => $flag:expr) => {
-        if $crate::keyboard::debounce(&$rl, $key, &mut $k, $debounce_map) {
-            $flag = true;
-            check_collision(&mut $state, &$delta);
-            if $state.path.is_full() {
-                $state.path.pop_front();
+impl InputBuffer {
+    pub fn poll(
+        &mut self,
+        rl: &mut RaylibHandle,
+        bindings: &Bindings,
+        gamepad: &Gamepad,
+    ) -> Option<KeyboardKey> {
+        let mut first_key = None;
+        let mut actions = vec![];
+        while let Some(key) = rl.get_key_pressed() {
+            first_key.get_or_insert(key);
+            actions.extend(bindings.action(rl, key));
+        }
+        actions.extend(gamepad.action(rl, bindings));
+
+        let mut pressed = Pos(0, 0);
+        for action in actions {
+            match action.delta() {
+                Some(delta) => pressed = pressed + delta.into(),
+                None => self.push(Command::Act(action)),
             }
-            if !$state.path.contains(&$state.player.pos) {
-                $state.path.push_back($state.player.pos);
+        }
+        let held = GameAction::ALL
+            .into_iter()
+            .filter(|&action| {
+                bindings
+                    .keys(action)
+                    .any(|chord| rl.is_key_down(chord.key) && chord.modifiers_held(rl))
+                    || gamepad.held(rl, bindings, action)
+            })
+            .filter_map(|action| action.delta())
+            .fold(Pos(0, 0), |acc, delta| acc + delta.into());
+        let held = clamp(held);
+        if pressed != Pos(0, 0) {
+            let step = clamp(pressed + held);
+            if step != Pos(0, 0) {
+                self.push(Command::Move(step));
             }
         }
-    };
+        if self.repeat.update(held, rl.get_time()) {
+            self.push(Command::Move(held));
+        }
+        first_key
+    }
+
+    pub fn push(&mut self, command: Command) {
+        self.queue.push_back(command);
+    }
+
+    pub fn next(&mut self) -> Option<Command> {
+        self.queue.pop_front()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
+
+fn clamp(pos: Pos) -> Pos {
+    Pos(pos.0.clamp(-1, 1), pos.1.clamp(-1, 1))
 }
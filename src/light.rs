@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use raylib::prelude::Color;
+use symmetric_shadowcasting::{compute_fov, Pos as SPos};
+
+use crate::{distance, state::State, utils::Pos};
+
+pub const TORCH_RADIUS: f32 = 7.0;
+const LIT_THRESHOLD: f32 = 0.05;
+const AMBIENT: f32 = 0.15;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightSource {
+    pub pos: Pos,
+    pub radius: f32,
+    pub color: Color,
+    pub intensity: f32,
+}
+
+impl LightSource {
+    pub fn torch(pos: Pos) -> Self {
+        Self {
+            pos,
+            radius: TORCH_RADIUS,
+            color: Color::new(255, 200, 140, 255),
+            intensity: 1.0,
+        }
+    }
+
+    pub fn teleporter(pos: Pos) -> Self {
+        Self {
+            pos,
+            radius: 3.0,
+            color: Color::VIOLET,
+            intensity: 0.8,
+        }
+    }
+
+    pub fn exit(pos: Pos) -> Self {
+        Self {
+            pos,
+            radius: 4.0,
+            color: Color::GREEN,
+            intensity: 0.9,
+        }
+    }
+
+    pub fn brazier(pos: Pos) -> Self {
+        Self {
+            pos,
+            radius: 5.0,
+            color: Color::ORANGE,
+            intensity: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LightMap {
+    light: HashMap<Pos, [f32; 3]>,
+}
+
+impl LightMap {
+    pub fn compute(state: &State) -> Self {
+        let mut map = Self::default();
+        let sources = std::iter::once(LightSource::torch(state.player.pos))
+            .chain(
+                state
+                    .teleporters_map
+                    .keys()
+                    .map(|&p| LightSource::teleporter(p)),
+            )
+            .chain(std::iter::once(LightSource::exit(state.exit)))
+            .chain(state.braziers.iter().map(|&p| LightSource::brazier(p)));
+        for source in sources {
+            map.add(state, &source);
+        }
+        map
+    }
+
+    fn add(&mut self, state: &State, source: &LightSource) {
+        let mut lit = vec![];
+        let mut is_blocking = |pos: SPos| {
+            distance(pos.into(), source.pos) > source.radius || state.blocks_sight(&pos.into())
+        };
+        let mut mark_visible = |pos: SPos| lit.push(Pos::from(pos));
+        compute_fov(source.pos.as_tuple(), &mut is_blocking, &mut mark_visible);
+        for pos in lit {
+            let falloff = (1.0 - distance(pos, source.pos) / source.radius).max(0.0);
+            let strength = falloff * falloff * source.intensity;
+            let channels = [source.color.r, source.color.g, source.color.b];
+            let acc = self.light.entry(pos).or_default();
+            for (acc, channel) in acc.iter_mut().zip(channels) {
+                *acc += channel as f32 / 255.0 * strength;
+            }
+        }
+    }
+
+    pub fn brightness(&self, pos: &Pos) -> f32 {
+        self.light
+            .get(pos)
+            .map_or(0.0, |c| c.iter().cloned().fold(0.0, f32::max))
+    }
+
+    pub fn is_lit(&self, pos: &Pos) -> bool {
+        self.brightness(pos) > LIT_THRESHOLD
+    }
+
+    pub fn tint(&self, pos: &Pos) -> Option<Color> {
+        let [r, g, b] = self.light.get(pos)?;
+        let channel = |c: f32| ((c + AMBIENT).min(1.0) * 255.0) as u8;
+        Some(Color::new(channel(*r), channel(*g), channel(*b), 255))
+    }
+}
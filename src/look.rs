@@ -0,0 +1,87 @@
+use crate::{
+    item::{Action, Item, ItemType},
+    state::State,
+    utils::{Door, Pos},
+    Block,
+};
+
+/// Describes what the player knows about `pos`; enemies and items only show while visible.
+pub fn describe(state: &State, pos: Pos, visible: bool) -> Vec<String> {
+    if !visible && !state.explored.contains(&pos) {
+        return vec!["You can't see there".to_string()];
+    }
+    let mut lines = vec![];
+    if pos == state.player.pos {
+        let mut status = vec![format!("HP {}/{}", state.player.hp, state.player.max_hp)];
+        status.extend(state.player.statuses.names().map(str::to_string));
+        lines.push(format!("You ({})", status.join(", ")));
+    }
+    if visible {
+        if let Some(enemy) = state.enemies.get(&pos) {
+            let mut status = vec![enemy.ai.name(), enemy.archetype.walker().pattern.name()];
+            status.extend(enemy.statuses.names());
+            if enemy.archetype.opens_doors() {
+                status.push("opens doors");
+            }
+            lines.push(format!(
+                "{} (HP {}/{}, {})",
+                enemy.name(),
+                enemy.hp,
+                enemy.max_hp,
+                status.join(", ")
+            ));
+        }
+        if let Some(item) = state.items.get(&pos) {
+            lines.push(describe_item(item));
+        }
+    }
+    lines.push(match state.map.get(&pos) {
+        Some(Block::Wall) => "Wall".to_string(),
+        Some(Block::Exit) if state.exit_sealed() => "Stairs down (sealed)".to_string(),
+        Some(Block::Exit) => "Stairs down".to_string(),
+        Some(Block::Door(Door::Open)) => "Open door".to_string(),
+        Some(Block::Door(Door::Closed)) => "Closed door".to_string(),
+        Some(Block::Door(Door::Locked(_))) => "Locked door".to_string(),
+        Some(Block::Teleporter(_)) | None => match state.describe_teleporter(&pos) {
+            Some(teleporter) => teleporter,
+            None => match state.terrain.get(&pos) {
+                Some(terrain) => capitalize(terrain.name()),
+                None => "Floor".to_string(),
+            },
+        },
+    });
+    if let Some(trap) = state.traps.get(&pos).filter(|t| t.revealed) {
+        lines.push(capitalize(trap.kind.name()));
+    }
+    if state.braziers.contains(&pos) {
+        lines.push("Brazier".to_string());
+    }
+    lines
+}
+
+fn describe_item(item: &Item) -> String {
+    let ty = match item.ty() {
+        ItemType::Melee => "melee weapon",
+        ItemType::Ingredient => "ingredient",
+        ItemType::HealPotion => "potion",
+        ItemType::Key(_) => "key",
+        ItemType::Wand(_) => "wand",
+    };
+    let actions: Vec<String> = [(Action::Damage, "damage"), (Action::Heal, "heal")]
+        .into_iter()
+        .filter(|(action, _)| item.value(action) > 0)
+        .map(|(action, name)| format!("{} {}", name, item.value(&action)))
+        .collect();
+    if actions.is_empty() {
+        format!("{} ({})", item.name(), ty)
+    } else {
+        format!("{} ({}, {})", item.name(), ty, actions.join(", "))
+    }
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    chars.next().map_or(String::new(), |c| {
+        c.to_uppercase().collect::<String>() + chars.as_str()
+    })
+}
@@ -1,182 +1,578 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, env, path::Path, process};
 
 use raylib::prelude::*;
+mod ability;
+mod boss;
+use ability::Ability;
 mod enemy;
 mod keyboard;
+use keyboard::{Command, InputBuffer};
 mod sprite_sheet;
-use enemy::Enemy;
+use enemy::{Act, Enemy};
 
 mod state;
-use state::{EventType, State};
+use state::{State, COMBAT_NOISE};
 mod item;
 mod player;
 use player::Player;
 
 mod draw;
-use draw::{draw_end_screen, draw_log, draw_main_screen, draw_ui};
+use draw::{draw_help, draw_log, draw_main_screen, draw_screen, draw_ui};
 
 mod components;
 use components::GameComponents;
 mod utils;
-use utils::{check_collision, distance, Block};
+use utils::{check_collision, distance, Block, Pos};
+mod dijkstra;
+mod teleporter;
+mod terrain;
+use terrain::{Terrain, FALL_DAMAGE};
+mod traps;
+mod travel;
+use travel::{AutoMove, Travel};
+mod bindings;
+mod config;
+use bindings::{Bindings, GameAction};
+mod gamepad;
+use gamepad::Gamepad;
+mod screens;
+use screens::{read_menu_input, Screen, Transition};
+mod message_log;
+use message_log::LogView;
+mod events;
+use events::{Actor, GameEvent};
+mod effects;
+use effects::Effects;
+mod camera;
+mod light;
+mod look;
+mod motion;
+mod movement;
+mod pathing;
+use motion::Motion;
+use pathing::Pathing;
+mod morgue;
+mod replay;
+use replay::{FrameInput, Playback, Replay};
+mod scores;
+mod settings;
+use settings::Settings;
 
 fn main() {
-    let width = 1024;
-    let height = 768;
-    let debounce_map: &mut HashMap<KeyboardKey, u32> = &mut HashMap::new();
-    let mut player = Player::new((1, 1).into());
+    let mut settings = Settings::load();
+    let (width, height) = settings.size();
+    let mut player = Player::new((1, 1).into(), &mut rand::thread_rng());
     let mut state = State::new(&mut player);
-    state.reset();
 
     let (mut rl, thread) = raylib::init()
         .size(width, height)
-        .title("Hello, World")
+        .title("Roguer Ebert")
         .build();
     rl.set_target_fps(60);
+    rl.set_exit_key(None);
+    let audio = RaylibAudio::init_audio_device()
+        .map_err(|e| eprintln!("Could not open audio device: {:?}", e))
+        .ok();
     let tex = rl.load_texture(&thread, "tilemap.png").expect("texture");
     let mut components =
         GameComponents::new(&tex, Vector2::new(width as f32, (height / 3 * 2) as f32));
+    let mut auto: Option<AutoMove> = None;
+    let bindings = Bindings::load();
+    let mut gamepad = Gamepad::default();
+    let mut input = InputBuffer::default();
+    settings.apply(&mut rl, &mut components, &mut input, audio.as_ref());
+    let mut screens = vec![Screen::title(false)];
+    let mut run_over = false;
+    let mut quit = false;
 
-    while !rl.window_should_close() {
-        if state.player.hp <= 0 {
-            {
-                draw_end_screen(&mut rl, &thread, &mut state, &components);
-                continue;
+    let mut effects = Effects::default();
+    let mut motion = Motion::default();
+    let mut recorder: Option<Replay> = None;
+    let mut replay_failed = false;
+    let mut playback = match env::args().skip_while(|arg| arg != "--replay").nth(1) {
+        Some(path) => match Replay::load(Path::new(&path)) {
+            Ok(replay) => Some(Playback::new(replay)),
+            Err(e) => {
+                eprintln!("Could not load replay {}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => None,
+    };
+    if let Some(playback) = &playback {
+        state.new_run(playback.replay.seed);
+        screens.clear();
+    }
+
+    while !rl.window_should_close() && !quit {
+        if let Some(message) = gamepad.poll(&rl) {
+            state.emit(GameEvent::Info(message));
+        }
+        let (width, height) = settings.size();
+        if let Some(screen) = screens.last_mut() {
+            let can_continue = state.dungeon.is_some() && !run_over;
+            let transition = read_menu_input(&mut rl, &gamepad, &bindings)
+                .map_or(Transition::Stay, |menu| {
+                    screen.update(menu, &mut settings, can_continue)
+                });
+            match transition {
+                Transition::Stay => {}
+                Transition::Push(screen) => screens.push(screen),
+                Transition::Pop => {
+                    screens.pop();
+                }
+                Transition::ToTitle => {
+                    screens = vec![Screen::title(can_continue)];
+                }
+                Transition::NewGame(seed) => {
+                    if let Some(replay) = recorder.take() {
+                        eprintln!("{}", replay.finish(&state));
+                    }
+                    recorder = Some(Replay::new(seed));
+                    state.new_run(seed);
+                    components.camera.snap();
+                    auto = None;
+                    components.look = None;
+                    components.picking_teleporter = None;
+                    // Replays start from the defaults, so recorded runs must too.
+                    components.target = 0;
+                    components.debug = false;
+                    input.clear();
+                    run_over = false;
+                    screens.clear();
+                }
+                Transition::SettingsChanged => {
+                    settings.apply(&mut rl, &mut components, &mut input, audio.as_ref());
+                }
+                Transition::Quit => quit = true,
+            }
+            let mut d = rl.begin_drawing(&thread);
+            match screens.last() {
+                Some(screen) => draw_screen(
+                    &mut d,
+                    screen,
+                    &settings,
+                    &bindings,
+                    can_continue,
+                    &Rectangle::new(0.0, 0.0, width as f32, height as f32),
+                ),
+                None => d.clear_background(Color::BLACK),
             }
+            continue;
         }
-        match &state.player.state {
-            player::PlayerState::Walking => {
-                let mut k = 1;
-                debounce_key_move!(KeyboardKey::KEY_A => (-1, 0).into() => rl => k => debounce_map => state => components.active_turn);
-                debounce_key_move!(KeyboardKey::KEY_W => (0, -1).into() => rl => k => debounce_map => state => components.active_turn);
-                debounce_key_move!(KeyboardKey::KEY_D => (1, 0).into() => rl => k => debounce_map => state => components.active_turn);
-                debounce_key_move!(KeyboardKey::KEY_S => (0, 1).into() => rl => k => debounce_map => state => components.active_turn);
-                debounce_key_move!(KeyboardKey::KEY_UP => (0, -1).into() => rl => k => debounce_map => state => components.active_turn);
-                debounce_key_move!(KeyboardKey::KEY_DOWN => (0, 1).into() => rl => k => debounce_map => state => components.active_turn);
-                debounce_key_move!(KeyboardKey::KEY_LEFT => (-1, 0).into() => rl => k => debounce_map => state => components.active_turn);
-                debounce_key_move!(KeyboardKey::KEY_RIGHT => (1, 0).into() => rl => k => debounce_map => state => components.active_turn);
-            }
-            player::PlayerState::Combat(e) => {
-                let pressed_key = rl.get_key_pressed();
-                if let Some(k) = pressed_key {
-                    match k {
-                        KeyboardKey::KEY_P => {
-                            let p = e.first().unwrap();
-                            if let Some(enemy) = state.enemies.get_mut(p) {
-                                let old_hp = enemy.hp;
-                                let item = state.player.carrying.clone();
-                                item.apply(state.player, enemy);
-                                let damage = old_hp - enemy.hp;
-                                state.event(
-                                    format!("You attacked an enemy for {} damage", damage),
-                                    EventType::DamageDealt,
-                                );
-                                components.active_turn = true;
-                            } else {
-                                println!("Enemy not found");
+        let mut won = false;
+        if playback
+            .as_mut()
+            .is_none_or(|playback| playback.controls(&mut rl))
+        {
+            let frame = match playback.as_mut() {
+                Some(playback) => playback.next(),
+                None => FrameInput {
+                    key: input.poll(&mut rl, &bindings, &gamepad),
+                    command: input.next(),
+                },
+            };
+            if let Some(replay) = recorder.as_mut() {
+                replay.record(frame);
+            }
+            let FrameInput {
+                key: pressed_key,
+                mut command,
+            } = frame;
+            let mut picked = None;
+            if let Some(selected) = components.picking_teleporter {
+                let count = state.known_teleporters().len();
+                components.picking_teleporter = match command.take() {
+                    _ if count == 0 => None,
+                    Some(Command::Move(Pos(x, y))) if x + y < 0 => {
+                        Some((selected + count - 1) % count)
+                    }
+                    Some(Command::Move(_)) => Some((selected + 1) % count),
+                    Some(Command::Act(GameAction::TravelTeleporter | GameAction::Attack)) => {
+                        picked = state.known_teleporters().get(selected).copied();
+                        None
+                    }
+                    Some(Command::Act(GameAction::Pause)) => None,
+                    _ => Some(selected),
+                };
+            }
+            if let Some(cursor) = components.look {
+                components.look = match command.take() {
+                    Some(Command::Move(delta)) => Some(cursor + delta),
+                    Some(Command::Act(GameAction::Inspect | GameAction::Pause)) => None,
+                    _ => Some(cursor),
+                };
+            }
+            let action = match command {
+                Some(Command::Act(action)) => Some(action),
+                _ => None,
+            };
+            match &state.player.state {
+                player::PlayerState::Walking if auto.is_some() => {
+                    let mv = auto.as_mut().unwrap();
+                    match mv.next_step(&state, components.auto_pickup) {
+                        Ok(Some(delta)) => {
+                            state.walk(&delta);
+                            if components.auto_pickup {
+                                state.pick_up();
                             }
-                        }
-                        KeyboardKey::KEY_ONE => {
                             components.active_turn = true;
-                            let _ = state.player.equip(0);
                         }
-                        KeyboardKey::KEY_TWO => {
-                            components.active_turn = true;
-                            let _ = state.player.equip(1);
+                        Ok(None) => {}
+                        Err(interrupt) => {
+                            state.emit(GameEvent::Info(interrupt.message(&bindings)));
+                            auto = None;
                         }
-                        KeyboardKey::KEY_THREE => {
+                    }
+                }
+                player::PlayerState::Walking => {
+                    if let Some(Command::Move(delta)) = command {
+                        components.active_turn = true;
+                        state.walk(&delta);
+                    }
+                }
+                player::PlayerState::Combat(e) => match action {
+                    Some(GameAction::Attack) => {
+                        let p = &e[components.target % e.len()];
+                        if let Some(enemy) = state.enemies.get_mut(p) {
+                            let old_hp = enemy.hp;
+                            let item = state.player.carrying.clone();
+                            item.apply(state.player, enemy);
+                            let damage = old_hp - enemy.hp;
+                            if damage > 0 {
+                                enemy.last_attacker = Some(Actor::Player);
+                            }
+                            let target = Actor::Enemy(enemy.name().to_string());
+                            let pos = enemy.pos;
+                            state.make_noise(state.player.pos, COMBAT_NOISE);
+                            state.emit(GameEvent::Attack {
+                                attacker: Actor::Player,
+                                target,
+                                amount: damage,
+                                pos,
+                            });
                             components.active_turn = true;
-                            let _ = state.player.equip(2);
+                        } else {
+                            println!("Enemy not found");
                         }
-                        KeyboardKey::KEY_FOUR => {
+                    }
+                    Some(GameAction::CycleTarget) => {
+                        components.target = (components.target + 1) % e.len();
+                    }
+                    Some(GameAction::Equip(i)) => {
+                        components.active_turn = true;
+                        state.equip(i);
+                    }
+                    _ => {}
+                },
+            }
+
+            if (pressed_key.is_some() || command.is_some()) && auto.take().is_some() {
+                input.clear();
+                state.emit(GameEvent::Info("You stop".to_string()));
+            } else if let Some(teleporter) = picked {
+                auto = Some(AutoMove::new(Travel::Teleporter(teleporter), &state));
+            } else if let Some(action) = action {
+                match action {
+                    GameAction::Explore => {
+                        auto = Some(AutoMove::new(Travel::Explore, &state));
+                    }
+                    GameAction::TravelExit => {
+                        auto = Some(AutoMove::new(Travel::Exit, &state));
+                    }
+                    GameAction::TravelTeleporter => {
+                        if state.known_teleporters().is_empty() {
+                            state.emit(GameEvent::Info(
+                                "You don't know any teleporters".to_string(),
+                            ));
+                        } else {
+                            components.picking_teleporter = Some(0);
+                            state.emit(GameEvent::Info(format!(
+                                "Travel to which teleporter? (move to choose, {} to go, {} to cancel)",
+                                bindings.label(GameAction::TravelTeleporter),
+                                bindings.label(GameAction::Pause)
+                            )));
+                        }
+                    }
+                    GameAction::Use
+                        if state.terrain.get(&state.player.pos) == Some(&Terrain::DeepWater) =>
+                    {
+                        state.emit(GameEvent::Info(
+                            "You can't use items while swimming".to_string(),
+                        ));
+                    }
+                    GameAction::Use => match state.player.use_carried() {
+                        Some(heal) => {
+                            state.emit(GameEvent::Heal { amount: heal });
                             components.active_turn = true;
-                            let _ = state.player.equip(3);
                         }
-                        _ => {}
+                        None => {
+                            let name = state.player.carrying.name().clone();
+                            state.emit(GameEvent::Info(format!("You can't use {}", name)));
+                        }
+                    },
+                    GameAction::NextItem => {
+                        components.active_turn |= state.equip(0);
+                    }
+                    GameAction::PickUp => {
+                        components.active_turn |= state.pick_up();
+                    }
+                    GameAction::Inspect => {
+                        components.look = Some(state.player.pos);
+                        state.emit(GameEvent::Info(format!(
+                            "Look where? ({} or {} to stop)",
+                            bindings.label(GameAction::Inspect),
+                            bindings.label(GameAction::Pause)
+                        )));
+                    }
+                    GameAction::Search => {
+                        if state.search(true) == 0 {
+                            state.emit(GameEvent::Info("You find nothing".to_string()));
+                        }
+                        components.active_turn = true;
+                    }
+                    GameAction::Disarm => {
+                        components.active_turn |= state.disarm();
+                    }
+                    GameAction::Fire => match state.player.carrying.ability() {
+                        Some(ability @ Ability::Bolt { range, .. }) => {
+                            let target = match &state.player.state {
+                                player::PlayerState::Combat(targets) => {
+                                    Some(targets[components.target % targets.len()])
+                                }
+                                player::PlayerState::Walking => {
+                                    let from = state.player.pos;
+                                    state
+                                        .compute_enemies()
+                                        .into_iter()
+                                        .filter(|&p| distance(from, p) <= range)
+                                        .filter(|&p| state.line_of_fire(from, p))
+                                        .min_by(|a, b| {
+                                            distance(from, *a).total_cmp(&distance(from, *b))
+                                        })
+                                }
+                            };
+                            match target {
+                                Some(target) => {
+                                    state.cast(Actor::Player, state.player.pos, ability, target);
+                                    components.active_turn = true;
+                                }
+                                None => {
+                                    state.emit(GameEvent::Info(
+                                        "Nothing in range to shoot".to_string(),
+                                    ));
+                                }
+                            }
+                        }
+                        _ => {
+                            let name = state.player.carrying.name().clone();
+                            state.emit(GameEvent::Info(format!("You can't shoot {}", name)));
+                        }
+                    },
+                    GameAction::ToggleDebug => {
+                        components.debug = !components.debug;
                     }
+                    GameAction::ZoomOut => {
+                        components.vfactor -= 1;
+                    }
+                    GameAction::ZoomIn => {
+                        components.vfactor += 1;
+                    }
+                    GameAction::ZoomReset => {
+                        components.vfactor = settings.zoom;
+                    }
+                    GameAction::SkipTurn if components.debug => {
+                        components.active_turn = true;
+                    }
+                    GameAction::Fullscreen => {
+                        rl.toggle_fullscreen();
+                        settings.fullscreen = rl.is_window_fullscreen();
+                    }
+                    GameAction::Help => {
+                        components.show_help = !components.show_help;
+                    }
+                    GameAction::MessageLog if playback.is_none() => {
+                        screens.push(Screen::Log(LogView::new(&state.log)));
+                    }
+                    GameAction::Pause if playback.is_none() => {
+                        screens.push(Screen::Pause(0));
+                    }
+                    _ => {}
                 }
             }
-        }
 
-        let pressed_key = rl.get_key_pressed();
-        if let Some(k) = pressed_key {
-            match k {
-                KeyboardKey::KEY_I => {
-                    for pos in state.player.pos.around() {
-                        if let Some(item) = state.items.get(&pos) {
-                            dbg!(item);
-                            break;
-                        }
-                    }
+            match state
+                .map
+                .get(&state.player.pos)
+                .or(state.teleporters_map.get(&state.player.pos))
+            {
+                Some(&Block::Wall) | Some(&Block::Door(_)) | Some(&Block::Teleporter(_)) => {}
+                Some(&Block::Exit) if state.exit_sealed() && components.active_turn => {
+                    auto = None;
+                    let name = state.boss().map_or("", |b| b.name()).to_string();
+                    state.emit(GameEvent::Info(format!(
+                        "The stairs are sealed while the {} lives",
+                        name
+                    )));
                 }
-                KeyboardKey::KEY_SPACE => {
-                    components.debug = !components.debug;
+                Some(&Block::Exit) if state.exit_sealed() => {}
+                Some(&Block::Exit) => {
+                    auto = None;
+                    won = state.descend();
                 }
-                KeyboardKey::KEY_MINUS => {
-                    components.vfactor -= 1;
+                None if state.terrain.get(&state.player.pos) == Some(&Terrain::Chasm)
+                    && !state.exit_sealed() =>
+                {
+                    auto = None;
+                    state.emit(GameEvent::Info("You fall into the chasm".to_string()));
+                    state.player.hp -= FALL_DAMAGE;
+                    state.emit(GameEvent::Attack {
+                        attacker: Actor::Environment("the fall".to_string()),
+                        target: Actor::Player,
+                        amount: FALL_DAMAGE,
+                        pos: state.player.pos,
+                    });
+                    won = state.descend();
                 }
-                KeyboardKey::KEY_EQUAL => {
-                    components.vfactor += 1;
+
+                None => {}
+            }
+            let mut pps: Vec<Pos> = vec![];
+            let mut pathing = Pathing::default();
+            // Kept up to date as enemies move so no two pick the same tile.
+            let mut k_enemies = HashMap::new();
+            if components.active_turn {
+                state.bury_dead();
+                state.tick_statuses();
+                state.bury_dead();
+                pps = state.enemies.keys().copied().collect();
+                pps.sort_by_key(|p| (p.1, p.0));
+                pathing = Pathing::new(&state);
+                k_enemies = state.enemies.clone();
+            }
+            let mut new_enemies = vec![];
+            let mut events = vec![];
+            let mut casts = vec![];
+            for pos in &pps {
+                let Some(senses) = state.enemies.get(pos).map(|e| state.senses(e)) else {
+                    continue;
+                };
+                let enemy = state.enemies.get_mut(pos).unwrap();
+                let act = enemy.update(
+                    state.player,
+                    &mut state.map,
+                    &k_enemies,
+                    &state.traps,
+                    &state.terrain,
+                    &pathing,
+                    senses,
+                    &mut state.rng,
+                );
+                match act {
+                    Some(Act::Melee(damage)) => events.push(GameEvent::Attack {
+                        attacker: Actor::Enemy(enemy.name().to_string()),
+                        target: Actor::Player,
+                        amount: damage,
+                        pos: state.player.pos,
+                    }),
+                    Some(Act::Cast(ability, target)) => {
+                        casts.push((enemy.name().to_string(), enemy.pos, ability, target));
+                    }
+                    None => {}
                 }
-                KeyboardKey::KEY_Q => {
-                    components.vfactor = 10;
+                if enemy.pos != *pos {
+                    events.push(GameEvent::Move {
+                        actor: Actor::Enemy(enemy.name().to_string()),
+                        from: *pos,
+                        to: enemy.pos,
+                    });
                 }
-                KeyboardKey::KEY_O if components.debug => {
-                    components.active_turn = true;
+                k_enemies.remove(pos);
+                k_enemies.insert(enemy.pos, enemy.clone());
+                new_enemies.push((*pos, enemy.clone()));
+                state.enemies.remove(pos);
+            }
+            if components.active_turn {
+                state.noises.clear();
+            }
+            for event in events {
+                state.emit(event);
+            }
+            let mut moved = vec![];
+            for (from, enemy) in new_enemies {
+                if enemy.pos != from {
+                    moved.push(enemy.pos);
                 }
-                KeyboardKey::KEY_F => {
-                    rl.toggle_fullscreen();
+                state.enemies.insert(enemy.pos, enemy);
+            }
+            // Everyone is back on the map first, so a teleport can't land on an enemy
+            // that hasn't been put back yet.
+            for pos in moved {
+                let mut enemy = state.enemies.remove(&pos).unwrap();
+                state.spring_trap(pos, Some(&mut enemy));
+                state.enter_terrain(pos, Some(&mut enemy));
+                state.use_teleporter(pos, Some(&mut enemy));
+                state.enemies.insert(enemy.pos, enemy);
+            }
+            for (name, from, ability, target) in casts {
+                if state.enemies.get(&from).is_some_and(|e| e.hp > 0) {
+                    state.cast(Actor::Enemy(name), from, ability, target);
                 }
-                _ => {}
             }
-        }
-
-        match state
-            .map
-            .get(&state.player.pos)
-            .or(state.teleporters_map.get(&state.player.pos))
-        {
-            Some(&Block::Wall) => {}
-            Some(&Block::Teleporter(p)) => {
-                state.event("Teleporter activated".to_string(), EventType::Teleport);
-                state.player.pos = p + (-1, -1).into();
+            if components.active_turn {
+                state.bury_dead();
+                state.update_bosses();
             }
-            Some(&Block::Exit) => {
-                state.reset();
+            if components.active_turn {
+                state.stats.turns += 1;
+                state.search(false);
             }
+            components.active_turn = false;
 
-            None => {}
+            state.update();
         }
-        let mut d = rl.begin_drawing(&thread);
-
-        let pps = state.compute_enemies();
-        let k_enemies = state.enemies.clone();
-        let mut new_enemies = vec![];
-        let mut logs = vec![];
-        for pos in &pps {
-            let enemy = state.enemies.get_mut(pos);
-            if enemy.is_none() {
-                continue;
-            }
-            let enemy = enemy.unwrap();
-            let damage = enemy.update(state.player, &state.map, &k_enemies, components.active_turn);
-            if let Some(damage) = damage {
-                logs.push(format!("Ghost hits you for {} damage", damage));
-            }
-            new_enemies.push(enemy.clone());
-            state.enemies.remove(pos);
-        }
-        for log in logs {
-            state.event(log, EventType::DamageTaken);
-        }
-        for enemy in new_enemies {
-            if enemy.hp > 0 {
-                state.enemies.insert(enemy.pos, enemy);
-            } else {
-                state.event("Enemy died".to_string(), EventType::XP);
-                state.player.xp += enemy.dificulty as i32;
+        effects.update(rl.get_time());
+        motion.update(rl.get_time());
+        state.bus.dispatch(&mut [
+            &mut state.stats,
+            &mut effects,
+            &mut motion,
+            &mut components.camera,
+        ]);
+        let finished = won || state.player.hp <= 0;
+        if let Some(playback) = &playback {
+            if finished || playback.done() {
+                let report = playback.verify(&state);
+                replay_failed = report.is_err();
+                screens.push(Screen::Replay(report.unwrap_or_else(|lines| lines)));
+            }
+        } else if finished {
+            auto = None;
+            run_over = true;
+            let mut recap = morgue::record(&state, won);
+            if let Some(replay) = recorder.take() {
+                recap.push(replay.finish(&state));
             }
+            screens.push(if won {
+                Screen::Victory(recap)
+            } else {
+                Screen::Dead(recap)
+            });
+        }
+        if components.look.is_some() && rl.get_mouse_delta() != Vector2::zero() {
+            let mouse = rl.get_mouse_position();
+            components.look = Some(components.camera.screen_to_tile(mouse, components.midpoint));
         }
+        let (map_width, map_height) = state.dimensions();
+        components.camera.update(
+            motion.player(state.player.pos),
+            components.vfactor as f32,
+            Vector2::new(map_width as f32, map_height as f32),
+            components.screen_size,
+            rl.get_frame_time(),
+        );
+        let mut d = rl.begin_drawing(&thread);
+
         let enemies = state.compute_enemies();
         let items = state.compute_items();
         draw_main_screen(
@@ -185,11 +581,15 @@ fn main() {
             &enemies,
             &items,
             &components,
+            &effects,
+            &motion,
             Rectangle::new(0.0, 0.0, width as f32, ((height / 3) * 2) as f32),
         );
         draw_ui(
             &mut d,
             &state,
+            &bindings,
+            &gamepad,
             &Rectangle::new(
                 0.0,
                 (height / 3 * 2) as f32,
@@ -207,8 +607,21 @@ fn main() {
                 height as f32,
             ),
         );
-        components.active_turn = false;
-
-        state.update();
+        if components.show_help {
+            draw_help(
+                &mut d,
+                &bindings,
+                &Rectangle::new(0.0, 0.0, width as f32, ((height / 3) * 2) as f32),
+            );
+        }
+        if let Some(playback) = &playback {
+            d.draw_text(&playback.status(), 10, 10, 20, Color::YELLOW);
+        }
+    }
+    if let Some(replay) = recorder.take() {
+        eprintln!("{}", replay.finish(&state));
+    }
+    if replay_failed {
+        process::exit(1);
     }
 }
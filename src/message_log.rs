@@ -0,0 +1,148 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    bindings::{Bindings, GameAction},
+    config::{data_dir, unique_path},
+    morgue::now,
+    state::EventType,
+};
+
+pub const LOG_ROWS: usize = 14;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    pub turn: u32,
+    pub message: String,
+    pub etype: EventType,
+    pub count: u32,
+}
+
+impl LogEntry {
+    pub fn text(&self) -> String {
+        if self.count > 1 {
+            format!("{} x{}", self.message, self.count)
+        } else {
+            self.message.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MessageLog {
+    entries: Vec<LogEntry>,
+}
+
+impl MessageLog {
+    pub fn push(&mut self, turn: u32, message: String, etype: EventType) {
+        if let Some(last) = self.entries.last_mut() {
+            if last.message == message && last.etype == etype {
+                last.count += 1;
+                last.turn = turn;
+                return;
+            }
+        }
+        self.entries.push(LogEntry {
+            turn,
+            message,
+            etype,
+            count: 1,
+        });
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn recent(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().rev()
+    }
+
+    pub fn entries(&self) -> &[LogEntry] {
+        &self.entries
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogView {
+    entries: Vec<LogEntry>,
+    pub scroll: usize,
+    pub filter: Option<EventType>,
+    pub status: Option<String>,
+}
+
+impl LogView {
+    pub fn new(log: &MessageLog) -> Self {
+        Self {
+            entries: log.entries().to_vec(),
+            scroll: 0,
+            filter: None,
+            status: None,
+        }
+    }
+
+    fn visible(&self) -> Vec<&LogEntry> {
+        self.entries
+            .iter()
+            .rev()
+            .filter(|entry| self.filter.is_none_or(|etype| entry.etype == etype))
+            .collect()
+    }
+
+    pub fn scroll(&mut self, delta: isize) {
+        let max = self.visible().len().saturating_sub(LOG_ROWS);
+        self.scroll = self.scroll.saturating_add_signed(delta).min(max);
+    }
+
+    pub fn cycle_filter(&mut self, delta: isize) {
+        let options: Vec<Option<EventType>> = std::iter::once(None)
+            .chain(EventType::ALL.into_iter().map(Some))
+            .collect();
+        let current = options.iter().position(|f| *f == self.filter).unwrap_or(0);
+        let next = (current as isize + delta).rem_euclid(options.len() as isize) as usize;
+        self.filter = options[next];
+        self.scroll = 0;
+    }
+
+    pub fn lines(&self, bindings: &Bindings) -> Vec<String> {
+        let filter = match self.filter {
+            Some(etype) => format!("< {:?} >", etype),
+            None => "< All messages >".to_string(),
+        };
+        let mut lines = vec![filter];
+        lines.extend(
+            self.visible()
+                .iter()
+                .skip(self.scroll)
+                .take(LOG_ROWS)
+                .map(|entry| format!("[{:>4}] {}", entry.turn, entry.text())),
+        );
+        lines.push(self.status.clone().unwrap_or(format!(
+            "({}) Export  ({}) Back",
+            bindings.label(GameAction::ExportLog),
+            bindings.label(GameAction::Pause)
+        )));
+        lines
+    }
+
+    pub fn export(&mut self) {
+        self.status = Some(match self.write() {
+            Ok(path) => format!("Exported to {}", path.display()),
+            Err(e) => format!("Could not export log: {}", e),
+        });
+    }
+
+    fn write(&self) -> Result<PathBuf, String> {
+        let (date, time) = now();
+        let dir = data_dir().ok_or("no data directory")?.join("logs");
+        let stem = format!("log-{}-{}", date.replace('-', ""), time.replace(':', ""));
+        let path = unique_path(&dir, &stem);
+        let mut content = String::new();
+        for entry in self.entries.iter() {
+            content += &format!("[{:>4}] {:?}: {}\n", entry.turn, entry.etype, entry.text());
+        }
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, content))
+            .map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
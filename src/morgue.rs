@@ -0,0 +1,187 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config::{data_dir, unique_path},
+    events::{Actor, GameEvent, Subscriber},
+    scores::{HighScores, Score},
+    state::State,
+};
+
+#[derive(Debug, Default, Clone)]
+pub struct RunStats {
+    pub turns: u32,
+    pub kills: u32,
+    pub killed_by: Option<String>,
+}
+
+impl Subscriber for RunStats {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::EnemyDied {
+                killer: Some(Actor::Player),
+                ..
+            } => self.kills += 1,
+            GameEvent::Attack {
+                attacker: Actor::Enemy(name),
+                target: Actor::Player,
+                amount,
+                ..
+            } if *amount > 0 => self.killed_by = Some(format!("a {}", name)),
+            GameEvent::Attack {
+                attacker: Actor::Environment(cause),
+                target: Actor::Player,
+                amount,
+                ..
+            } if *amount > 0 => self.killed_by = Some(cause.clone()),
+            _ => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Recap {
+    pub seed: u64,
+    pub depth: u32,
+    pub turns: u32,
+    pub xp: i32,
+    pub kills: u32,
+    pub won: bool,
+    pub date: String,
+    pub time: String,
+    pub cause: String,
+    pub carrying: String,
+    pub items: Vec<String>,
+    pub log: Vec<String>,
+}
+
+impl Recap {
+    pub fn new(state: &State, won: bool) -> Self {
+        let cause = if won {
+            "Escaped the dungeon".to_string()
+        } else {
+            let killer = state.stats.killed_by.as_deref().unwrap_or("something");
+            format!("Killed by {} on depth {}", killer, state.depth)
+        };
+        let (date, time) = now();
+        Self {
+            seed: state.seed,
+            depth: state.depth,
+            turns: state.stats.turns,
+            xp: state.player.xp,
+            kills: state.stats.kills,
+            won,
+            date,
+            time,
+            cause,
+            carrying: state.player.carrying.name().clone(),
+            items: state
+                .player
+                .items
+                .iter()
+                .map(|i| i.name().clone())
+                .collect(),
+            log: state
+                .log
+                .recent()
+                .take(20)
+                .map(|entry| format!("[{}] {}", entry.turn, entry.text()))
+                .collect(),
+        }
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let items = if self.items.is_empty() {
+            "nothing".to_string()
+        } else {
+            self.items.join(", ")
+        };
+        vec![
+            self.cause.clone(),
+            format!(
+                "Depth {}, {} turns, {} XP, {} kills",
+                self.depth, self.turns, self.xp, self.kills
+            ),
+            format!("Carrying {}, pack: {}", self.carrying, items),
+            format!("Seed {}", self.seed),
+        ]
+    }
+
+    pub fn write(&self) -> Result<PathBuf, String> {
+        let dir = data_dir().ok_or("no data directory")?.join("morgue");
+        let stem = format!(
+            "morgue-{}-{}",
+            self.date.replace('-', ""),
+            self.time.replace(':', "")
+        );
+        let path = unique_path(&dir, &stem);
+        let mut content = format!(
+            "Roguer Ebert morgue file\n{} {} UTC\n\n",
+            self.date, self.time
+        );
+        for line in self.lines() {
+            content += &format!("{}\n", line);
+        }
+        content += "\nPack:\n";
+        for item in &self.items {
+            content += &format!("  {}\n", item);
+        }
+        content += "\nLast messages:\n";
+        for message in &self.log {
+            content += &format!("  {}\n", message);
+        }
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, content))
+            .map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+}
+
+pub fn record(state: &State, won: bool) -> Vec<String> {
+    let recap = Recap::new(state, won);
+    let mut lines = recap.lines();
+    match recap.write() {
+        Ok(path) => lines.push(format!("Morgue file: {}", path.display())),
+        Err(e) => lines.push(format!("Could not write morgue file: {}", e)),
+    }
+    let mut scores = HighScores::load();
+    let rank = scores.add(Score::new(&recap));
+    lines.push(format!("Rank {} on seed {}", rank, recap.seed));
+    lines.extend(
+        scores
+            .for_seed(recap.seed)
+            .take(3)
+            .enumerate()
+            .map(|(i, s)| format!("{}. {}", i + 1, s.summary())),
+    );
+    lines
+}
+
+pub fn now() -> (String, String) {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    timestamp(secs)
+}
+
+fn timestamp(secs: u64) -> (String, String) {
+    let days = (secs / 86400) as i64;
+    let rem = secs % 86400;
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (
+        format!("{:04}-{:02}-{:02}", year, month, day),
+        format!("{:02}:{:02}:{:02}", rem / 3600, rem % 3600 / 60, rem % 60),
+    )
+}
@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use raylib::prelude::Vector2;
+
+use crate::{
+    events::{Actor, GameEvent, Subscriber},
+    utils::Pos,
+};
+
+const TWEEN_SECONDS: f64 = 0.12;
+
+#[derive(Debug, Clone, Copy)]
+struct Tween {
+    from: Vector2,
+    to: Pos,
+    start: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct Motion {
+    now: f64,
+    player: Option<Tween>,
+    enemies: HashMap<Pos, Tween>,
+}
+
+impl Motion {
+    pub fn update(&mut self, now: f64) {
+        self.now = now;
+        self.enemies.retain(|_, t| now - t.start < TWEEN_SECONDS);
+    }
+
+    fn interpolate(&self, tween: Option<&Tween>, pos: Pos) -> Vector2 {
+        let target = Vector2::new(pos.0 as f32, pos.1 as f32);
+        let Some(tween) = tween.filter(|t| t.to == pos) else {
+            return target;
+        };
+        let t = ((self.now - tween.start) / TWEEN_SECONDS).clamp(0.0, 1.0) as f32;
+        let t = t * (2.0 - t);
+        Vector2::new(
+            tween.from.x + (target.x - tween.from.x) * t,
+            tween.from.y + (target.y - tween.from.y) * t,
+        )
+    }
+
+    pub fn player(&self, pos: Pos) -> Vector2 {
+        self.interpolate(self.player.as_ref(), pos)
+    }
+
+    pub fn enemy(&self, pos: Pos) -> Vector2 {
+        self.interpolate(self.enemies.get(&pos), pos)
+    }
+}
+
+impl Subscriber for Motion {
+    fn notify(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Move {
+                actor: Actor::Player,
+                from,
+                to,
+            } => {
+                self.player = Some(Tween {
+                    from: self.player(*from),
+                    to: *to,
+                    start: self.now,
+                });
+            }
+            GameEvent::Move { from, to, .. } => {
+                let start = self.enemy(*from);
+                self.enemies.remove(from);
+                self.enemies.insert(
+                    *to,
+                    Tween {
+                        from: start,
+                        to: *to,
+                        start: self.now,
+                    },
+                );
+            }
+            GameEvent::Teleport { .. } | GameEvent::Descend { .. } => {
+                self.player = None;
+                self.enemies.clear();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::fmt::Debug;
+
+use crate::utils::{distance, Pos};
+
+const SLIDE_RANGE: isize = 4;
+const BLINK_RADIUS: isize = 3;
+
+/// How an enemy gets from one tile to the next. Moves must be symmetric, since the shared
+/// pathing maps are expanded outwards from the player.
+pub trait MovementPattern: Debug + Sync {
+    fn name(&self) -> &'static str;
+    /// Tiles reachable in a single move from `pos`, where `free` tells which tiles can be entered.
+    fn moves(&self, pos: Pos, free: &dyn Fn(Pos) -> bool) -> Vec<Pos>;
+}
+
+#[derive(Debug)]
+pub struct KingStep;
+
+impl MovementPattern for KingStep {
+    fn name(&self) -> &'static str {
+        "steps"
+    }
+    fn moves(&self, pos: Pos, free: &dyn Fn(Pos) -> bool) -> Vec<Pos> {
+        pos.around()
+            .into_iter()
+            .filter(|&p| p != pos && free(p))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct RookSlide;
+
+impl MovementPattern for RookSlide {
+    fn name(&self) -> &'static str {
+        "slides"
+    }
+    fn moves(&self, pos: Pos, free: &dyn Fn(Pos) -> bool) -> Vec<Pos> {
+        pos.adjacent()
+            .into_iter()
+            .flat_map(|next| {
+                let dir = next - pos;
+                (1..=SLIDE_RANGE)
+                    .map(move |n| pos + Pos(dir.0 * n, dir.1 * n))
+                    .take_while(|&p| free(p))
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct KnightJump;
+
+impl MovementPattern for KnightJump {
+    fn name(&self) -> &'static str {
+        "jumps"
+    }
+    fn moves(&self, pos: Pos, free: &dyn Fn(Pos) -> bool) -> Vec<Pos> {
+        [
+            (1, 2),
+            (1, -2),
+            (-1, 2),
+            (-1, -2),
+            (2, 1),
+            (2, -1),
+            (-2, 1),
+            (-2, -1),
+        ]
+        .into_iter()
+        .map(|(x, y)| pos + Pos(x, y))
+        .filter(|&p| free(p))
+        .collect()
+    }
+}
+
+/// Teleports to any free tile nearby, through walls.
+#[derive(Debug)]
+pub struct Blink;
+
+impl MovementPattern for Blink {
+    fn name(&self) -> &'static str {
+        "blinks"
+    }
+    fn moves(&self, pos: Pos, free: &dyn Fn(Pos) -> bool) -> Vec<Pos> {
+        (-BLINK_RADIUS..=BLINK_RADIUS)
+            .flat_map(|y| (-BLINK_RADIUS..=BLINK_RADIUS).map(move |x| pos + Pos(x, y)))
+            .filter(|&p| p != pos && distance(p, pos) <= BLINK_RADIUS as f32 && free(p))
+            .collect()
+    }
+}
+
+#[derive(Debug)]
+pub struct Turret;
+
+impl MovementPattern for Turret {
+    fn name(&self) -> &'static str {
+        "stationary"
+    }
+    fn moves(&self, _pos: Pos, _free: &dyn Fn(Pos) -> bool) -> Vec<Pos> {
+        vec![]
+    }
+}
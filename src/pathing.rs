@@ -0,0 +1,101 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    dijkstra::DijkstraMap,
+    enemy::Archetype,
+    movement::MovementPattern,
+    state::State,
+    terrain::Terrain,
+    traps::Trap,
+    utils::{Door, Pos},
+    Block,
+};
+
+/// How an enemy kind gets around.
+#[derive(Debug, Clone, Copy)]
+pub struct Walker {
+    pub pattern: &'static dyn MovementPattern,
+    pub opens_doors: bool,
+}
+
+impl Walker {
+    /// Cost of stepping onto `pos`, ignoring other enemies.
+    pub fn cost(
+        &self,
+        pos: Pos,
+        map: &HashMap<Pos, Block>,
+        traps: &HashMap<Pos, Trap>,
+        terrain: &HashMap<Pos, Terrain>,
+    ) -> Option<u32> {
+        let free = match map.get(&pos) {
+            None | Some(Block::Door(Door::Open)) => true,
+            Some(Block::Door(Door::Closed)) => self.opens_doors,
+            _ => false,
+        };
+        if !free || traps.get(&pos).is_some_and(|t| t.known_to_enemies) {
+            return None;
+        }
+        terrain.get(&pos).map_or(Some(1), Terrain::cost)
+    }
+
+    /// Moves out of `pos` with the cost of their landing tile; `blocked` rules out extra tiles.
+    pub fn successors(
+        &self,
+        pos: Pos,
+        map: &HashMap<Pos, Block>,
+        traps: &HashMap<Pos, Trap>,
+        terrain: &HashMap<Pos, Terrain>,
+        blocked: &dyn Fn(Pos) -> bool,
+    ) -> Vec<(Pos, u32)> {
+        let free = |p: Pos| !blocked(p) && self.cost(p, map, traps, terrain).is_some();
+        self.pattern
+            .moves(pos, &free)
+            .into_iter()
+            .filter_map(|p| Some((p, self.cost(p, map, traps, terrain)?)))
+            .collect()
+    }
+}
+
+/// Distance maps to and away from the player, built once per turn for every archetype on the floor.
+#[derive(Debug, Default)]
+pub struct Pathing {
+    bounds: (u32, u32),
+    chase: HashMap<Archetype, DijkstraMap>,
+    flee: HashMap<Archetype, DijkstraMap>,
+}
+
+impl Pathing {
+    pub fn new(state: &State) -> Self {
+        let archetypes: HashSet<Archetype> = state.enemies.values().map(|e| e.archetype).collect();
+        let mut pathing = Self {
+            bounds: state.dimensions(),
+            ..Self::default()
+        };
+        for archetype in archetypes {
+            let walker = archetype.walker();
+            // Expanding out from the player charges for each tile entered on the way out,
+            // a close enough stand-in for the walk back in.
+            let successors = |pos: Pos| {
+                walker.successors(pos, &state.map, &state.traps, &state.terrain, &|p| {
+                    !state.in_bounds(&p)
+                })
+            };
+            let chase = DijkstraMap::new([state.player.pos], successors);
+            pathing.flee.insert(archetype, chase.flee(successors));
+            pathing.chase.insert(archetype, chase);
+        }
+        pathing
+    }
+
+    pub fn in_bounds(&self, pos: &Pos) -> bool {
+        (0..self.bounds.0 as isize).contains(&pos.0) && (0..self.bounds.1 as isize).contains(&pos.1)
+    }
+
+    pub fn chase(&self, archetype: &Archetype) -> Option<&DijkstraMap> {
+        self.chase.get(archetype)
+    }
+
+    pub fn flee(&self, archetype: &Archetype) -> Option<&DijkstraMap> {
+        self.flee.get(archetype)
+    }
+}
@@ -1,10 +1,11 @@
 use std::collections::HashMap;
 
-use crate::item::{Action, EditableEntity, Item, Value};
+use crate::ability::Statuses;
+use crate::item::{Action, EditableEntity, Item, ItemType, Value};
 use crate::utils::Pos;
 use rand::Rng;
 
-use crate::{distance, enemy::Enemy};
+use crate::distance;
 
 #[derive(Debug)]
 pub enum PlayerState {
@@ -25,6 +26,8 @@ pub struct Player {
     pub xp: i32,
     swing: u8,
     pub distance: f32,
+    pub wading: bool,
+    pub statuses: Statuses,
 }
 
 impl EditableEntity for Player {
@@ -38,8 +41,8 @@ impl EditableEntity for Player {
 }
 
 impl Player {
-    pub fn new(pos: Pos) -> Self {
-        let luck = rand::thread_rng().gen_range(5..15);
+    pub fn new(pos: Pos, rng: &mut impl Rng) -> Self {
+        let luck = rng.gen_range(5..15);
         Self {
             hp: 100,
             max_hp: 100,
@@ -50,15 +53,21 @@ impl Player {
             state: PlayerState::Walking,
             carrying: Item::new(
                 "Sword".into(),
-                crate::item::ItemType::Melee,
+                ItemType::Melee,
                 HashMap::from([(Action::Damage, 30 as Value)]),
             ),
             items: vec![],
             swing: 0,
             distance: 30.0,
+            wading: false,
+            statuses: Statuses::default(),
         }
     }
 
+    pub fn luck(&self) -> i32 {
+        self.luck
+    }
+
     pub fn cicle_swing(&mut self) {
         self.swing = self.swing.wrapping_add(1) % 4;
     }
@@ -73,13 +82,6 @@ impl Player {
         self.hp -= remaining_damage;
     }
 
-    #[must_use]
-    pub fn attack(&mut self, enemy: &mut Enemy) -> i32 {
-        let damage = rand::thread_rng().gen_range(0..self.luck + 1);
-        enemy.hit_by(damage);
-        damage
-    }
-
     pub fn equip(&mut self, i: usize) -> Result<(), ()> {
         let this = &self.items.clone();
         let item = this.get(i);
@@ -93,6 +95,43 @@ impl Player {
         Err(())
     }
 
+    pub fn use_carried(&mut self) -> Option<Value> {
+        let ItemType::HealPotion = self.carrying.ty() else {
+            return None;
+        };
+        let heal = self.carrying.value(&Action::Heal);
+        self.heal(&heal);
+        self.hp = self.hp.min(self.max_hp);
+        self.carrying = if self.items.is_empty() {
+            Item::fists()
+        } else {
+            self.items.remove(0)
+        };
+        Some(heal)
+    }
+
+    pub fn has_key(&self, id: u32) -> bool {
+        std::iter::once(&self.carrying)
+            .chain(&self.items)
+            .any(|item| matches!(item.ty(), ItemType::Key(k) if *k == id))
+    }
+
+    pub fn take_key(&mut self, id: u32) -> Option<Item> {
+        let is_key = |item: &Item| matches!(item.ty(), ItemType::Key(k) if *k == id);
+        if let Some(i) = self.items.iter().position(is_key) {
+            return Some(self.items.remove(i));
+        }
+        if !is_key(&self.carrying) {
+            return None;
+        }
+        let next = if self.items.is_empty() {
+            Item::fists()
+        } else {
+            self.items.remove(0)
+        };
+        Some(std::mem::replace(&mut self.carrying, next))
+    }
+
     pub fn check_sourroundings(&mut self, enemies: &Vec<Pos>) {
         let mut out = vec![];
         for &pos in enemies {
@@ -0,0 +1,243 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use raylib::{ffi::KeyboardKey, prelude::key_from_i32, RaylibHandle};
+
+use crate::{
+    bindings::GameAction,
+    config::{data_dir, unique_path},
+    keyboard::Command,
+    morgue::now,
+    state::State,
+    utils::Pos,
+};
+
+const HEADER: &str = "roguer-ebert replay 1";
+const SPEEDS: [u32; 5] = [1, 2, 4, 8, 16];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameInput {
+    pub key: Option<KeyboardKey>,
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Replay {
+    pub seed: u64,
+    pub frames: u64,
+    pub hash: u64,
+    inputs: Vec<(u64, FrameInput)>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            ..Default::default()
+        }
+    }
+
+    pub fn record(&mut self, input: FrameInput) {
+        if input.key.is_some() || input.command.is_some() {
+            self.inputs.push((self.frames, input));
+        }
+        self.frames += 1;
+    }
+
+    pub fn finish(mut self, state: &State) -> String {
+        self.hash = state_hash(state);
+        match self.save() {
+            Ok(path) => format!("Replay: {}", path.display()),
+            Err(e) => format!("Could not write replay: {}", e),
+        }
+    }
+
+    fn save(&self) -> Result<PathBuf, String> {
+        let (date, time) = now();
+        let dir = data_dir().ok_or("no data directory")?.join("replays");
+        let stem = format!(
+            "replay-{}-{}-{}",
+            date.replace('-', ""),
+            time.replace(':', ""),
+            self.seed
+        );
+        let path = unique_path(&dir, &stem);
+        let mut content = format!("{}\nseed {}\n", HEADER, self.seed);
+        for (frame, input) in &self.inputs {
+            let key = input
+                .key
+                .map_or("-".to_string(), |key| (key as i32).to_string());
+            let command = match input.command {
+                Some(Command::Move(Pos(x, y))) => format!("m {} {}", x, y),
+                Some(Command::Act(action)) => format!("a {}", action.name()),
+                None => "-".to_string(),
+            };
+            content += &format!("{} {} {}\n", frame, key, command);
+        }
+        content += &format!("end {} {}\n", self.frames, self.hash);
+        fs::create_dir_all(&dir)
+            .and_then(|_| fs::write(&path, content))
+            .map_err(|e| e.to_string())?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let mut lines = content.lines();
+        if lines.next() != Some(HEADER) {
+            return Err("not a replay file".to_string());
+        }
+        let mut replay = Self::default();
+        for (n, line) in lines.enumerate() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let parsed = match fields.as_slice() {
+                ["seed", seed] => seed.parse().map(|seed| replay.seed = seed).ok(),
+                ["end", frames, hash] => frames
+                    .parse()
+                    .ok()
+                    .zip(hash.parse().ok())
+                    .map(|(frames, hash)| (replay.frames, replay.hash) = (frames, hash)),
+                [frame, key, command @ ..] => parse_input(key, command).and_then(|input| {
+                    replay.inputs.push((frame.parse().ok()?, input));
+                    Some(())
+                }),
+                _ => None,
+            };
+            if parsed.is_none() {
+                return Err(format!("invalid line {}: {}", n + 2, line));
+            }
+        }
+        Ok(replay)
+    }
+}
+
+fn parse_input(key: &str, command: &[&str]) -> Option<FrameInput> {
+    let key = match key {
+        "-" => None,
+        key => Some(key_from_i32(key.parse().ok()?)?),
+    };
+    let command = match command {
+        ["-"] => None,
+        ["m", x, y] => Some(Command::Move(Pos(x.parse().ok()?, y.parse().ok()?))),
+        ["a", name] => Some(Command::Act(GameAction::from_name(name)?)),
+        _ => return None,
+    };
+    Some(FrameInput { key, command })
+}
+
+#[derive(Debug)]
+pub struct Playback {
+    pub replay: Replay,
+    frame: u64,
+    cursor: usize,
+    paused: bool,
+    speed: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            frame: 0,
+            cursor: 0,
+            paused: false,
+            speed: 0,
+        }
+    }
+
+    pub fn controls(&mut self, rl: &mut RaylibHandle) -> bool {
+        let mut step = false;
+        while let Some(key) = rl.get_key_pressed() {
+            match key {
+                KeyboardKey::KEY_SPACE => self.paused = !self.paused,
+                KeyboardKey::KEY_N | KeyboardKey::KEY_RIGHT => step = true,
+                KeyboardKey::KEY_MINUS => self.speed = self.speed.saturating_sub(1),
+                KeyboardKey::KEY_EQUAL => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+                _ => {}
+            }
+        }
+        rl.set_target_fps(60 * SPEEDS[self.speed]);
+        !self.done() && (!self.paused || step)
+    }
+
+    pub fn next(&mut self) -> FrameInput {
+        let input = match self.replay.inputs.get(self.cursor) {
+            Some(&(frame, input)) if frame == self.frame => {
+                self.cursor += 1;
+                input
+            }
+            _ => FrameInput {
+                key: None,
+                command: None,
+            },
+        };
+        self.frame += 1;
+        input
+    }
+
+    pub fn done(&self) -> bool {
+        self.frame >= self.replay.frames
+    }
+
+    /// Checks the final state against the recorded hash; either way the lines describe the outcome.
+    pub fn verify(&self, state: &State) -> Result<Vec<String>, Vec<String>> {
+        let hash = state_hash(state);
+        let matches = hash == self.replay.hash;
+        let result = if matches {
+            "State hash matches".to_string()
+        } else {
+            format!(
+                "State hash MISMATCH: expected {}, got {}",
+                self.replay.hash, hash
+            )
+        };
+        eprintln!("{}", result);
+        let lines = vec![
+            format!("Seed {}, {} frames", self.replay.seed, self.frame),
+            result,
+        ];
+        if matches {
+            Ok(lines)
+        } else {
+            Err(lines)
+        }
+    }
+
+    pub fn status(&self) -> String {
+        format!(
+            "Replay {}/{} frames  {}x{}  (Space) pause (N) step (-/=) speed",
+            self.frame,
+            self.replay.frames,
+            SPEEDS[self.speed],
+            if self.paused { " paused" } else { "" }
+        )
+    }
+}
+
+pub fn state_hash(state: &State) -> u64 {
+    let mut enemies: Vec<_> = state
+        .enemies
+        .values()
+        .map(|e| (e.pos.as_tuple(), e.hp))
+        .collect();
+    enemies.sort();
+    let mut items: Vec<_> = state.items.keys().map(|p| p.as_tuple()).collect();
+    items.sort();
+    let summary = format!(
+        "{:?} {} {} {} {} {} {:?} {:?}",
+        state.player.pos.as_tuple(),
+        state.player.hp,
+        state.player.xp,
+        state.depth,
+        state.stats.turns,
+        state.stats.kills,
+        enemies,
+        items
+    );
+    // FNV-1a, so hashes stay comparable across builds.
+    summary.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
@@ -0,0 +1,157 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::{config::data_dir, morgue::Recap};
+
+const SCORES_FILE: &str = "scores.txt";
+const HEADER: &str = "# score seed depth turns xp kills won date\n";
+pub const TABLE_SIZE: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Score {
+    pub score: u32,
+    pub seed: u64,
+    pub depth: u32,
+    pub turns: u32,
+    pub xp: i32,
+    pub kills: u32,
+    pub won: bool,
+    pub date: String,
+}
+
+impl Score {
+    pub fn new(recap: &Recap) -> Self {
+        let score = (recap.xp.max(0) as u32 * 10 + recap.depth * 100 + recap.kills * 25)
+            .saturating_sub(recap.turns / 10)
+            + if recap.won { 1000 } else { 0 };
+        Self {
+            score,
+            seed: recap.seed,
+            depth: recap.depth,
+            turns: recap.turns,
+            xp: recap.xp,
+            kills: recap.kills,
+            won: recap.won,
+            date: recap.date.clone(),
+        }
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        Some(Self {
+            score: fields.next()?.parse().ok()?,
+            seed: fields.next()?.parse().ok()?,
+            depth: fields.next()?.parse().ok()?,
+            turns: fields.next()?.parse().ok()?,
+            xp: fields.next()?.parse().ok()?,
+            kills: fields.next()?.parse().ok()?,
+            won: fields.next()?.parse().ok()?,
+            date: fields.next()?.to_string(),
+        })
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{:>6}  depth {:>2}  {:>4} XP  {:>3} kills  {:>5} turns  {}{}",
+            self.score,
+            self.depth,
+            self.xp,
+            self.kills,
+            self.turns,
+            self.date,
+            if self.won { "  escaped" } else { "" }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HighScores {
+    scores: Vec<Score>,
+}
+
+impl HighScores {
+    pub fn load() -> Self {
+        let scores = path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| {
+                content
+                    .lines()
+                    .filter(|line| !line.starts_with('#'))
+                    .filter_map(Score::parse)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut table = Self { scores };
+        table.sort();
+        table.trim();
+        table
+    }
+
+    fn sort(&mut self) {
+        self.scores.sort_by_key(|s| std::cmp::Reverse(s.score));
+    }
+
+    /// Keeps only the best `TABLE_SIZE` runs overall and for each seed, as those are all
+    /// the tables ever show.
+    fn trim(&mut self) {
+        let mut per_seed: HashMap<u64, usize> = HashMap::new();
+        let mut rank = 0;
+        self.scores.retain(|s| {
+            rank += 1;
+            let count = per_seed.entry(s.seed).or_default();
+            *count += 1;
+            rank <= TABLE_SIZE || *count <= TABLE_SIZE
+        });
+    }
+
+    pub fn save(&self) {
+        let Some(path) = path() else {
+            return;
+        };
+        let mut content = String::from(HEADER);
+        for s in &self.scores {
+            content += &format!(
+                "{} {} {} {} {} {} {} {}\n",
+                s.score, s.seed, s.depth, s.turns, s.xp, s.kills, s.won, s.date
+            );
+        }
+        let written = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| fs::write(&path, content));
+        if let Err(e) = written {
+            eprintln!("Could not write {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn add(&mut self, score: Score) -> usize {
+        self.scores.push(score.clone());
+        self.sort();
+        let rank = self
+            .for_seed(score.seed)
+            .position(|s| *s == score)
+            .unwrap_or_default()
+            + 1;
+        self.trim();
+        self.save();
+        rank
+    }
+
+    pub fn seeds(&self) -> Vec<u64> {
+        let mut seeds: Vec<u64> = self.scores.iter().map(|s| s.seed).collect();
+        seeds.sort();
+        seeds.dedup();
+        seeds
+    }
+
+    pub fn all(&self) -> impl Iterator<Item = &Score> {
+        self.scores.iter()
+    }
+
+    pub fn for_seed(&self, seed: u64) -> impl Iterator<Item = &Score> {
+        self.scores.iter().filter(move |s| s.seed == seed)
+    }
+}
+
+fn path() -> Option<PathBuf> {
+    data_dir().map(|dir| dir.join(SCORES_FILE))
+}
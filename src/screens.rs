@@ -0,0 +1,403 @@
+use raylib::RaylibHandle;
+
+use crate::{
+    bindings::{Bindings, GameAction},
+    gamepad::Gamepad,
+    message_log::LogView,
+    scores::{HighScores, TABLE_SIZE},
+    settings::{Settings, WINDOW_SIZES},
+};
+
+const MAX_SEED_DIGITS: usize = 19;
+const TITLE_OPTIONS: [&str; 5] = ["Continue", "New game", "High scores", "Settings", "Quit"];
+const PAUSE_OPTIONS: [&str; 4] = ["Resume", "Settings", "Quit to title", "Quit game"];
+const SETTINGS_ROWS: usize = 9;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Screen {
+    Title(usize),
+    NewGame(String),
+    Settings(usize),
+    Pause(usize),
+    HighScores(HighScores, usize),
+    Log(LogView),
+    Dead(Vec<String>),
+    Victory(Vec<String>),
+    Replay(Vec<String>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MenuInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Confirm,
+    Back,
+    Erase,
+    Export,
+    NewRun,
+    Char(char),
+}
+
+/// Menus reuse the gameplay bindings so rebinding a key applies everywhere.
+const MENU_ACTIONS: [(GameAction, MenuInput); 9] = [
+    (GameAction::MoveUp, MenuInput::Up),
+    (GameAction::MoveDown, MenuInput::Down),
+    (GameAction::MoveLeft, MenuInput::Left),
+    (GameAction::MoveRight, MenuInput::Right),
+    (GameAction::Attack, MenuInput::Confirm),
+    (GameAction::Pause, MenuInput::Back),
+    (GameAction::Erase, MenuInput::Erase),
+    (GameAction::ExportLog, MenuInput::Export),
+    (GameAction::NewRun, MenuInput::NewRun),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Transition {
+    Stay,
+    Push(Screen),
+    Pop,
+    ToTitle,
+    NewGame(u64),
+    SettingsChanged,
+    Quit,
+}
+
+impl Screen {
+    pub fn title(can_continue: bool) -> Self {
+        Screen::Title(if can_continue { 0 } else { 1 })
+    }
+
+    pub fn update(
+        &mut self,
+        input: MenuInput,
+        settings: &mut Settings,
+        can_continue: bool,
+    ) -> Transition {
+        match self {
+            Screen::Title(selected) => match input {
+                MenuInput::Up | MenuInput::Down => {
+                    *selected = step(*selected, input, TITLE_OPTIONS.len());
+                    Transition::Stay
+                }
+                MenuInput::Confirm => match selected {
+                    0 if can_continue => Transition::Pop,
+                    1 => Transition::Push(Screen::NewGame(String::new())),
+                    2 => Transition::Push(Screen::HighScores(HighScores::load(), 0)),
+                    3 => Transition::Push(Screen::Settings(0)),
+                    4 => Transition::Quit,
+                    _ => Transition::Stay,
+                },
+                MenuInput::Back if can_continue => Transition::Pop,
+                _ => Transition::Stay,
+            },
+            Screen::NewGame(seed) => match input {
+                MenuInput::Char(c) if c.is_ascii_digit() && seed.len() < MAX_SEED_DIGITS => {
+                    seed.push(c);
+                    Transition::Stay
+                }
+                MenuInput::Erase => {
+                    seed.pop();
+                    Transition::Stay
+                }
+                MenuInput::Confirm => {
+                    Transition::NewGame(seed.parse().unwrap_or_else(|_| rand::random()))
+                }
+                MenuInput::Back => Transition::Pop,
+                _ => Transition::Stay,
+            },
+            Screen::Settings(selected) => match input {
+                MenuInput::Up | MenuInput::Down => {
+                    *selected = step(*selected, input, SETTINGS_ROWS);
+                    Transition::Stay
+                }
+                MenuInput::Left | MenuInput::Right => {
+                    let sign = if input == MenuInput::Left { -1 } else { 1 };
+                    if adjust(settings, *selected, sign) {
+                        Transition::SettingsChanged
+                    } else {
+                        Transition::Stay
+                    }
+                }
+                MenuInput::Confirm if matches!(*selected, 1 | 7) => {
+                    adjust(settings, *selected, 1);
+                    Transition::SettingsChanged
+                }
+                MenuInput::Confirm if *selected == SETTINGS_ROWS - 1 => {
+                    settings.save();
+                    Transition::Pop
+                }
+                MenuInput::Back => {
+                    settings.save();
+                    Transition::Pop
+                }
+                _ => Transition::Stay,
+            },
+            Screen::Pause(selected) => match input {
+                MenuInput::Up | MenuInput::Down => {
+                    *selected = step(*selected, input, PAUSE_OPTIONS.len());
+                    Transition::Stay
+                }
+                MenuInput::Confirm => match selected {
+                    0 => Transition::Pop,
+                    1 => Transition::Push(Screen::Settings(0)),
+                    2 => Transition::ToTitle,
+                    _ => Transition::Quit,
+                },
+                MenuInput::Back => Transition::Pop,
+                _ => Transition::Stay,
+            },
+            Screen::HighScores(scores, page) => match input {
+                MenuInput::Left | MenuInput::Right => {
+                    let pages = scores.seeds().len() + 1;
+                    *page = step(*page, input, pages);
+                    Transition::Stay
+                }
+                MenuInput::Confirm | MenuInput::Back => Transition::Pop,
+                _ => Transition::Stay,
+            },
+            Screen::Log(view) => match input {
+                MenuInput::Up => {
+                    view.scroll(-1);
+                    Transition::Stay
+                }
+                MenuInput::Down => {
+                    view.scroll(1);
+                    Transition::Stay
+                }
+                MenuInput::Left | MenuInput::Right => {
+                    view.cycle_filter(if input == MenuInput::Left { -1 } else { 1 });
+                    Transition::Stay
+                }
+                MenuInput::Export => {
+                    view.export();
+                    Transition::Stay
+                }
+                MenuInput::Confirm | MenuInput::Back => Transition::Pop,
+                _ => Transition::Stay,
+            },
+            Screen::Dead(_) => match input {
+                MenuInput::NewRun => Transition::NewGame(rand::random()),
+                MenuInput::Confirm | MenuInput::Back => Transition::ToTitle,
+                _ => Transition::Stay,
+            },
+            Screen::Victory(_) => match input {
+                MenuInput::Confirm | MenuInput::Back => Transition::ToTitle,
+                _ => Transition::Stay,
+            },
+            Screen::Replay(_) => match input {
+                MenuInput::Confirm | MenuInput::Back => Transition::Quit,
+                _ => Transition::Stay,
+            },
+        }
+    }
+
+    pub fn heading(&self) -> &str {
+        match self {
+            Screen::Title(_) => "ROGUER EBERT",
+            Screen::NewGame(_) => "NEW GAME",
+            Screen::Settings(_) => "SETTINGS",
+            Screen::Pause(_) => "PAUSED",
+            Screen::HighScores(..) => "HIGH SCORES",
+            Screen::Log(_) => "MESSAGE LOG",
+            Screen::Dead(_) => "DEAD",
+            Screen::Victory(_) => "YOU ESCAPED",
+            Screen::Replay(_) => "REPLAY FINISHED",
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        match self {
+            Screen::Title(selected) | Screen::Settings(selected) | Screen::Pause(selected) => {
+                Some(*selected)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn lines(
+        &self,
+        settings: &Settings,
+        bindings: &Bindings,
+        can_continue: bool,
+    ) -> Vec<(String, bool)> {
+        let confirm = bindings.label(GameAction::Attack);
+        let back = bindings.label(GameAction::Pause);
+        match self {
+            Screen::Title(_) => TITLE_OPTIONS
+                .iter()
+                .enumerate()
+                .map(|(i, option)| (option.to_string(), i != 0 || can_continue))
+                .collect(),
+            Screen::NewGame(seed) => vec![
+                (format!("Seed: {}_", seed), true),
+                ("Leave empty for a random seed".to_string(), false),
+                (format!("({}) Start  ({}) Back", confirm, back), false),
+            ],
+            Screen::Settings(_) => {
+                let (width, height) = settings.size();
+                vec![
+                    (format!("Window size: < {}x{} >", width, height), true),
+                    (
+                        format!("Fullscreen: < {} >", on_off(settings.fullscreen)),
+                        true,
+                    ),
+                    (format!("Zoom: < {} >", settings.zoom), true),
+                    (
+                        format!("Key repeat delay: < {} ms >", settings.repeat_delay_ms),
+                        true,
+                    ),
+                    (
+                        format!("Key repeat rate: < {} ms >", settings.repeat_rate_ms),
+                        true,
+                    ),
+                    (format!("Volume: < {}% >", settings.volume), true),
+                    (
+                        format!("Camera dead zone: < {} tiles >", settings.camera_dead_zone),
+                        true,
+                    ),
+                    (
+                        format!("Camera clamp: < {} >", on_off(settings.camera_clamp)),
+                        true,
+                    ),
+                    ("Back".to_string(), true),
+                ]
+            }
+            Screen::Pause(_) => PAUSE_OPTIONS
+                .iter()
+                .map(|option| (option.to_string(), true))
+                .collect(),
+            Screen::HighScores(scores, page) => {
+                let (title, table): (String, Vec<_>) = match page.checked_sub(1) {
+                    None => ("< All seeds >".to_string(), scores.all().collect()),
+                    Some(i) => {
+                        let seed = scores.seeds()[i];
+                        (
+                            format!("< Seed {} >", seed),
+                            scores.for_seed(seed).collect(),
+                        )
+                    }
+                };
+                let mut lines = vec![(title, true), (String::new(), false)];
+                if table.is_empty() {
+                    lines.push(("No runs recorded yet".to_string(), false));
+                }
+                lines.extend(
+                    table
+                        .iter()
+                        .take(TABLE_SIZE)
+                        .enumerate()
+                        .map(|(i, s)| (format!("{:>2}. {}", i + 1, s.summary()), true)),
+                );
+                lines
+            }
+            Screen::Log(view) => view
+                .lines(bindings)
+                .into_iter()
+                .map(|line| (line, true))
+                .collect(),
+            Screen::Dead(recap) => recap
+                .iter()
+                .map(|line| (line.clone(), true))
+                .chain([
+                    (String::new(), false),
+                    (
+                        format!("({}) New run", bindings.label(GameAction::NewRun)),
+                        false,
+                    ),
+                    (format!("({}) Back to title", confirm), false),
+                ])
+                .collect(),
+            Screen::Victory(recap) => recap
+                .iter()
+                .map(|line| (line.clone(), true))
+                .chain([
+                    (String::new(), false),
+                    (format!("({}) Back to title", confirm), false),
+                ])
+                .collect(),
+            Screen::Replay(result) => result
+                .iter()
+                .map(|line| (line.clone(), true))
+                .chain([
+                    (String::new(), false),
+                    (format!("({}) Quit", confirm), false),
+                ])
+                .collect(),
+        }
+    }
+}
+
+pub fn read_menu_input(
+    rl: &mut RaylibHandle,
+    gamepad: &Gamepad,
+    bindings: &Bindings,
+) -> Option<MenuInput> {
+    let key = rl.get_key_pressed();
+    let c = rl.get_char_pressed();
+    let bound = key.and_then(|key| {
+        MENU_ACTIONS
+            .into_iter()
+            .find(|&(action, _)| {
+                bindings
+                    .keys(action)
+                    .any(|chord| chord.key == key && chord.modifiers_held(rl))
+            })
+            .map(|(_, input)| input)
+    });
+    if let Some(input) = bound.or(c.map(MenuInput::Char)) {
+        return Some(input);
+    }
+    let id = gamepad.id?;
+    MENU_ACTIONS
+        .into_iter()
+        .find(|&(action, _)| {
+            bindings
+                .buttons(action)
+                .any(|button| rl.is_gamepad_button_pressed(id, button))
+        })
+        .map(|(_, input)| input)
+}
+
+fn step(selected: usize, input: MenuInput, len: usize) -> usize {
+    match input {
+        MenuInput::Up | MenuInput::Left => (selected + len - 1) % len,
+        _ => (selected + 1) % len,
+    }
+}
+
+fn adjust(settings: &mut Settings, row: usize, sign: i32) -> bool {
+    let old = settings.clone();
+    match row {
+        0 => {
+            settings.window_size = (settings.window_size as i32 + sign)
+                .clamp(0, WINDOW_SIZES.len() as i32 - 1) as usize
+        }
+        1 => settings.fullscreen = !settings.fullscreen,
+        2 => settings.zoom = (settings.zoom + 4 * sign as isize).clamp(8, 96),
+        3 => {
+            settings.repeat_delay_ms =
+                (settings.repeat_delay_ms as i32 + 25 * sign).clamp(50, 1000) as u32
+        }
+        4 => {
+            settings.repeat_rate_ms =
+                (settings.repeat_rate_ms as i32 + 10 * sign).clamp(10, 500) as u32
+        }
+        5 => settings.volume = (settings.volume as i32 + 10 * sign).clamp(0, 100) as u32,
+        6 => {
+            settings.camera_dead_zone = (settings.camera_dead_zone as i32 + sign).clamp(0, 4) as u32
+        }
+        7 => settings.camera_clamp = !settings.camera_clamp,
+        _ => {}
+    }
+    *settings != old
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value {
+        "On"
+    } else {
+        "Off"
+    }
+}
@@ -0,0 +1,131 @@
+use raylib::{prelude::RaylibAudio, RaylibHandle};
+
+use crate::{
+    components::GameComponents,
+    config::{config_dir, read_pairs, write_pairs},
+    keyboard::InputBuffer,
+};
+
+const SETTINGS_FILE: &str = "settings.cfg";
+pub const WINDOW_SIZES: [(i32, i32); 4] = [(800, 600), (1024, 768), (1280, 960), (1600, 1200)];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub window_size: usize,
+    pub fullscreen: bool,
+    pub zoom: isize,
+    pub repeat_delay_ms: u32,
+    pub repeat_rate_ms: u32,
+    pub volume: u32,
+    pub camera_dead_zone: u32,
+    pub camera_clamp: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            window_size: 1,
+            fullscreen: false,
+            zoom: 32,
+            repeat_delay_ms: 250,
+            repeat_rate_ms: 100,
+            volume: 80,
+            camera_dead_zone: 0,
+            camera_clamp: true,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        let mut settings = Self::default();
+        let Some(pairs) = config_dir().and_then(|dir| read_pairs(&dir.join(SETTINGS_FILE))) else {
+            return settings;
+        };
+        for (key, value) in pairs {
+            let parsed = match key.as_str() {
+                "window_size" => value
+                    .parse()
+                    .map(|v: usize| settings.window_size = v.min(WINDOW_SIZES.len() - 1))
+                    .is_ok(),
+                "fullscreen" => value.parse().map(|v| settings.fullscreen = v).is_ok(),
+                "zoom" => value
+                    .parse()
+                    .map(|v: isize| settings.zoom = v.clamp(8, 96))
+                    .is_ok(),
+                "repeat_delay_ms" => value
+                    .parse()
+                    .map(|v: u32| settings.repeat_delay_ms = v.clamp(50, 1000))
+                    .is_ok(),
+                "repeat_rate_ms" => value
+                    .parse()
+                    .map(|v: u32| settings.repeat_rate_ms = v.clamp(10, 500))
+                    .is_ok(),
+                "volume" => value
+                    .parse()
+                    .map(|v: u32| settings.volume = v.min(100))
+                    .is_ok(),
+                "camera_dead_zone" => value
+                    .parse()
+                    .map(|v: u32| settings.camera_dead_zone = v.min(4))
+                    .is_ok(),
+                "camera_clamp" => value.parse().map(|v| settings.camera_clamp = v).is_ok(),
+                _ => {
+                    eprintln!("Unknown setting: {}", key);
+                    continue;
+                }
+            };
+            if !parsed {
+                eprintln!("Invalid value for {}: {}", key, value);
+            }
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        let Some(path) = config_dir().map(|dir| dir.join(SETTINGS_FILE)) else {
+            return;
+        };
+        let pairs = [
+            ("window_size", self.window_size.to_string()),
+            ("fullscreen", self.fullscreen.to_string()),
+            ("zoom", self.zoom.to_string()),
+            ("repeat_delay_ms", self.repeat_delay_ms.to_string()),
+            ("repeat_rate_ms", self.repeat_rate_ms.to_string()),
+            ("volume", self.volume.to_string()),
+            ("camera_dead_zone", self.camera_dead_zone.to_string()),
+            ("camera_clamp", self.camera_clamp.to_string()),
+        ]
+        .map(|(key, value)| (key.to_string(), value));
+        if let Err(e) = write_pairs(&path, "", &pairs) {
+            eprintln!("Could not write {}: {}", path.display(), e);
+        }
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        WINDOW_SIZES[self.window_size]
+    }
+
+    pub fn apply(
+        &self,
+        rl: &mut RaylibHandle,
+        components: &mut GameComponents,
+        input: &mut InputBuffer,
+        audio: Option<&RaylibAudio>,
+    ) {
+        let (width, height) = self.size();
+        if rl.is_window_fullscreen() != self.fullscreen {
+            rl.toggle_fullscreen();
+        }
+        rl.set_window_size(width, height);
+        components.resize(width as f32, (height / 3 * 2) as f32);
+        components.vfactor = self.zoom;
+        components.camera.dead_zone = self.camera_dead_zone as f32;
+        components.camera.clamp = self.camera_clamp;
+        input.repeat.delay_ms = self.repeat_delay_ms;
+        input.repeat.rate_ms = self.repeat_rate_ms;
+        if let Some(audio) = audio {
+            audio.set_master_volume(self.volume as f32 / 100.0);
+        }
+    }
+}
@@ -22,10 +22,6 @@ impl SpriteSheet {
         (tiles_per_row * rows) as i32
     }
 
-    pub fn coords_to_rect(&self, x: i32, y: i32) -> Rectangle {
-        let index = y * self.tex_size.x as i32 + x;
-        self.index_to_rect(index)
-    }
     pub fn index_to_rect(&self, index: i32) -> Rectangle {
         let tiles_per_row = self.tex_size.x / (self.tile_size.x + self.offset as f32);
         let row = index / tiles_per_row as i32;
@@ -1,18 +1,56 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use crate::{item::Item, utils::Pos};
+use crate::{
+    ability::{Ability, SUMMON_HP},
+    boss::{BossKind, HAZARD_TILES},
+    enemy::{AiState, Senses},
+    events::{Actor, EventBus, GameEvent},
+    item::Item,
+    light::LightMap,
+    message_log::MessageLog,
+    morgue::RunStats,
+    teleporter::{Pad, COOLDOWN, DORMANT_CHANCE, ONE_WAY_CHANCE},
+    terrain::{Terrain, LAVA_DAMAGE, TERRAIN_CHANCE},
+    traps::{
+        Trap, TrapKind, ALARM_RADIUS, DART_DAMAGE, NOTICE_RADIUS, PLATE_RADIUS, SEARCH_RADIUS,
+    },
+    travel::travel_distance,
+    utils::{line, open_door, Door, Pos},
+};
 use bounded_vec_deque::BoundedVecDeque;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use symmetric_shadowcasting::{compute_fov, Pos as SPos};
-use tatami_dungeon::{Dungeon, GenerateDungeonParams, Tile};
+use tatami_dungeon::{Dungeon, Floor, GenerateDungeonParams, RoomKind, Tile};
 
-use crate::{distance, player::Player, Block, Enemy};
+use crate::{check_collision, distance, player::Player, Block, Enemy};
 
-#[derive(Debug)]
+pub const FINAL_DEPTH: u32 = 10;
+const LOCK_CHANCE: f64 = 0.25;
+const FOOTSTEP_NOISE: f32 = 2.0;
+const DOOR_NOISE: f32 = 6.0;
+const TRAP_NOISE: f32 = 4.0;
+pub const COMBAT_NOISE: f32 = 8.0;
+const FAR_DISTANCE: f32 = 16.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum EventType {
     DamageDealt,
     DamageTaken,
     Teleport,
     XP,
+    Item,
+    Info,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 6] = [
+        EventType::DamageDealt,
+        EventType::DamageTaken,
+        EventType::Teleport,
+        EventType::XP,
+        EventType::Item,
+        EventType::Info,
+    ];
 }
 
 #[derive(Debug)]
@@ -23,9 +61,27 @@ pub struct State<'a> {
     pub enemies: HashMap<Pos, Enemy>,
     pub items: HashMap<Pos, Item>,
     pub player: &'a mut Player,
-    pub log: BoundedVecDeque<(String, EventType)>,
+    pub log: MessageLog,
     pub path: BoundedVecDeque<Pos>,
+    pub explored: HashSet<Pos>,
     pub dungeon: Option<Dungeon>,
+    pub seed: u64,
+    pub depth: u32,
+    pub stats: RunStats,
+    pub rng: StdRng,
+    pub bus: EventBus,
+    pub light: LightMap,
+    pub braziers: Vec<Pos>,
+    pub traps: HashMap<Pos, Trap>,
+    pub terrain: HashMap<Pos, Terrain>,
+    pub pads: HashMap<Pos, Pad>,
+    pub noises: Vec<(Pos, f32)>,
+    /// Floor tiles of the boss room, empty on floors without a boss.
+    pub lair: Vec<Pos>,
+    /// Travel distance to the exit, refreshed once per turn for the sidebar.
+    pub exit_distance: Option<u32>,
+    exit_distance_turn: Option<u32>,
+    next_id: u32,
 }
 
 impl<'a> State<'a> {
@@ -36,24 +92,635 @@ impl<'a> State<'a> {
             items: Default::default(),
             teleporters_map: Default::default(),
             enemies: Default::default(),
-            log: BoundedVecDeque::new(8),
+            log: MessageLog::default(),
             path: BoundedVecDeque::new(300),
+            explored: Default::default(),
             dungeon: None,
             exit: (0, 0).into(),
+            seed: 0,
+            depth: 1,
+            stats: RunStats::default(),
+            rng: StdRng::seed_from_u64(0),
+            bus: EventBus::default(),
+            light: LightMap::default(),
+            braziers: vec![],
+            traps: Default::default(),
+            terrain: Default::default(),
+            pads: Default::default(),
+            noises: vec![],
+            lair: vec![],
+            exit_distance: None,
+            exit_distance_turn: None,
+            next_id: 0,
         }
     }
     pub fn update(&mut self) {
+        self.light = LightMap::compute(self);
         self.player.check_sourroundings(&self.compute_enemies());
+        let visible = self.compute_walls();
+        self.explored.extend(visible);
+        if self.exit_distance_turn != Some(self.stats.turns) {
+            self.exit_distance = travel_distance(self, self.exit);
+            self.exit_distance_turn = Some(self.stats.turns);
+        }
+    }
+    pub fn emit(&mut self, event: GameEvent) {
+        if let Some(message) = event.message() {
+            self.log.push(self.stats.turns, message, event.etype());
+        }
+        self.bus.publish(event);
+    }
+    pub fn walk(&mut self, delta: &Pos) {
+        let from = self.player.pos;
+        check_collision(self, delta);
+        if self.player.pos != from {
+            self.emit(GameEvent::Move {
+                actor: Actor::Player,
+                from,
+                to: self.player.pos,
+            });
+        }
+        if self.path.is_full() {
+            self.path.pop_front();
+        }
+        if !self.path.contains(&self.player.pos) {
+            self.path.push_back(self.player.pos);
+        }
+        if self.player.pos != from {
+            let terrain = self.terrain.get(&self.player.pos).copied();
+            if let Some(terrain) = terrain.filter(|t| Some(t) != self.terrain.get(&from)) {
+                if terrain != Terrain::Chasm {
+                    let message = format!("You step into the {}", terrain.name());
+                    self.emit(GameEvent::Info(message));
+                }
+            }
+            self.make_noise(self.player.pos, FOOTSTEP_NOISE);
+            self.spring_trap(self.player.pos, None);
+            self.enter_terrain(self.player.pos, None);
+            self.use_teleporter(self.player.pos, None);
+        }
+    }
+    /// Applies the terrain at `pos` to the player, or to `victim` if an enemy moved there.
+    pub fn enter_terrain(&mut self, pos: Pos, victim: Option<&mut Enemy>) {
+        if self.terrain.get(&pos) != Some(&Terrain::Lava) {
+            return;
+        }
+        let lava = Actor::Environment("a pool of lava".to_string());
+        let target = match victim {
+            Some(enemy) => {
+                enemy.hit_by(LAVA_DAMAGE, &lava);
+                Actor::Enemy(enemy.name().to_string())
+            }
+            None => {
+                self.player.hp -= LAVA_DAMAGE;
+                Actor::Player
+            }
+        };
+        self.emit(GameEvent::Attack {
+            attacker: lava,
+            target,
+            amount: LAVA_DAMAGE,
+            pos,
+        });
+    }
+    pub fn blocks_sight(&self, pos: &Pos) -> bool {
+        self.map.get(pos).is_some_and(Block::blocks_sight)
+            || self.terrain.get(pos).is_some_and(Terrain::blocks_sight)
+    }
+    pub fn make_noise(&mut self, pos: Pos, radius: f32) {
+        self.noises.push((pos, radius));
+    }
+    pub fn line_of_sight(&self, from: Pos, to: Pos) -> bool {
+        let tiles = line(from, to);
+        if tiles.len() <= 2 {
+            return true;
+        }
+        tiles[1..tiles.len() - 1]
+            .iter()
+            .all(|p| !self.blocks_sight(p))
+    }
+    /// The closest noise this turn loud enough for `enemy` to hear.
+    pub fn heard_by(&self, enemy: &Enemy) -> Option<Pos> {
+        let hearing = enemy.archetype.behaviour().hearing;
+        self.noises
+            .iter()
+            .filter(|(pos, radius)| distance(*pos, enemy.pos) <= radius * hearing)
+            .min_by(|a, b| distance(a.0, enemy.pos).total_cmp(&distance(b.0, enemy.pos)))
+            .map(|(pos, _)| *pos)
+    }
+    pub fn senses(&self, enemy: &Enemy) -> Senses {
+        let sight = enemy.archetype.behaviour().sight;
+        let range = distance(enemy.pos, self.player.pos);
+        Senses {
+            sees_player: range <= sight && self.line_of_sight(enemy.pos, self.player.pos),
+            heard: self.heard_by(enemy),
+            far: range > FAR_DISTANCE,
+            line_of_fire: range <= sight && self.line_of_fire(enemy.pos, self.player.pos),
+        }
+    }
+    /// The creature a projectile from `from` towards `to` stops on, if any within `range`.
+    pub fn bolt_hit(&self, from: Pos, to: Pos, range: f32) -> Option<Pos> {
+        line(from, to)
+            .into_iter()
+            .skip(1)
+            .take_while(|p| distance(from, *p) <= range && !self.blocks_sight(p))
+            .find(|p| *p == self.player.pos || self.enemies.contains_key(p))
+    }
+    pub fn line_of_fire(&self, from: Pos, to: Pos) -> bool {
+        self.bolt_hit(from, to, f32::MAX) == Some(to)
+    }
+    /// Resolves `ability` used by `caster` standing at `from` and aimed at `target`.
+    pub fn cast(&mut self, caster: Actor, from: Pos, ability: Ability, target: Pos) {
+        let seen = caster == Actor::Player || self.compute_walls().contains(&from);
+        match ability {
+            Ability::Bolt {
+                damage,
+                range,
+                status,
+            } => {
+                self.make_noise(from, COMBAT_NOISE);
+                let Some(hit) = self.bolt_hit(from, target, range) else {
+                    if caster == Actor::Player {
+                        self.emit(GameEvent::Info("Your bolt hits nothing".to_string()));
+                    }
+                    return;
+                };
+                let amount = self.rng.gen_range(1..=damage.max(1));
+                let victim = match self.enemies.get_mut(&hit) {
+                    Some(enemy) => {
+                        enemy.hit_by(amount, &caster);
+                        if let Some(status) = status {
+                            enemy.statuses.afflict(status);
+                        }
+                        Actor::Enemy(enemy.name().to_string())
+                    }
+                    None => {
+                        self.player.hit_by(amount);
+                        if let Some(status) = status {
+                            self.player.statuses.afflict(status);
+                        }
+                        Actor::Player
+                    }
+                };
+                self.emit(GameEvent::Attack {
+                    attacker: caster,
+                    target: victim,
+                    amount,
+                    pos: hit,
+                });
+            }
+            Ability::Mend { amount, radius } => {
+                for enemy in self.enemies.values_mut() {
+                    if enemy.hp > 0 && enemy.pos != from && distance(enemy.pos, from) <= radius {
+                        enemy.hp = (enemy.hp + amount).min(enemy.max_hp);
+                    }
+                }
+                if seen {
+                    self.emit(GameEvent::Info(format!(
+                        "The {} mends its allies",
+                        caster.name()
+                    )));
+                }
+            }
+            Ability::Summon(archetype) => {
+                let Some(pos) = self.free_tile_near(from) else {
+                    return;
+                };
+                let dificulty = self.enemies.get(&from).map_or(1, |e| e.dificulty);
+                let mut enemy = Enemy::of(archetype, SUMMON_HP, pos, dificulty, &mut self.rng);
+                enemy.ai = AiState::Hunting {
+                    last_seen: self.player.pos,
+                };
+                enemy.summoner = self.enemies.get(&from).map(|e| e.id);
+                self.spawn(enemy);
+                if seen {
+                    self.emit(GameEvent::Info(format!(
+                        "The {} summons a {}",
+                        caster.name(),
+                        archetype.name()
+                    )));
+                }
+            }
+        }
+    }
+    pub fn spawn(&mut self, mut enemy: Enemy) {
+        enemy.id = self.next_id;
+        self.next_id += 1;
+        self.enemies.insert(enemy.pos, enemy);
+    }
+    pub fn boss(&self) -> Option<&Enemy> {
+        self.enemies.values().find(|e| e.boss.is_some())
+    }
+    pub fn exit_sealed(&self) -> bool {
+        self.boss().is_some()
+    }
+    /// Moves bosses into the phase their HP calls for, bringing in adds and hazards.
+    pub fn update_bosses(&mut self) {
+        let mut changes: Vec<(Pos, usize)> = self
+            .enemies
+            .values()
+            .filter_map(|e| {
+                let boss = e.boss?;
+                let phase = boss.phase_at(e.hp, e.max_hp);
+                (phase > boss.phase).then_some((e.pos, phase))
+            })
+            .collect();
+        changes.sort_by_key(|(p, _)| (p.1, p.0));
+        for (pos, phase) in changes {
+            let enemy = self.enemies.get_mut(&pos).unwrap();
+            let Some(boss) = enemy.boss.as_mut() else {
+                continue;
+            };
+            boss.phase = phase;
+            let kind = boss.kind;
+            let current = boss.current();
+            let name = enemy.name().to_string();
+            self.emit(GameEvent::Info(current.announce.to_string()));
+            for _ in 0..current.adds {
+                self.cast(
+                    Actor::Enemy(name.clone()),
+                    pos,
+                    Ability::Summon(kind.archetype()),
+                    pos,
+                );
+            }
+            if let Some(hazard) = current.hazard {
+                self.spread_hazard(hazard);
+            }
+        }
+    }
+    fn spread_hazard(&mut self, hazard: Terrain) {
+        let mut tiles: Vec<Pos> = self
+            .lair
+            .iter()
+            .copied()
+            .filter(|p| {
+                *p != self.player.pos
+                    && *p != self.exit
+                    && !self.enemies.contains_key(p)
+                    && !self.items.contains_key(p)
+                    && !self.traps.contains_key(p)
+                    && !self.teleporters_map.contains_key(p)
+                    && !self.terrain.contains_key(p)
+                    && !p
+                        .around()
+                        .iter()
+                        .any(|n| matches!(self.map.get(n), Some(Block::Door(_))))
+            })
+            .collect();
+        tiles.sort_by_key(|p| (p.1, p.0));
+        for _ in 0..HAZARD_TILES.min(tiles.len()) {
+            let pos = tiles.swap_remove(self.rng.gen_range(0..tiles.len()));
+            self.terrain.insert(pos, hazard);
+        }
+    }
+    /// Removes enemies at 0 HP or below, awarding XP and boss loot.
+    pub fn bury_dead(&mut self) {
+        let mut dead: Vec<Pos> = self
+            .enemies
+            .values()
+            .filter(|e| e.hp <= 0)
+            .map(|e| e.pos)
+            .collect();
+        dead.sort_by_key(|p| (p.1, p.0));
+        for pos in dead {
+            let enemy = self.enemies.remove(&pos).unwrap();
+            self.player.xp += enemy.dificulty as i32;
+            self.emit(GameEvent::EnemyDied {
+                name: enemy.name().to_string(),
+                killer: enemy.last_attacker.clone(),
+                pos,
+                xp: enemy.dificulty as i32,
+            });
+            if let Some(boss) = enemy.boss {
+                self.defeat_boss(boss.kind, pos);
+            }
+        }
+    }
+    /// Drops the boss's loot where it fell and unseals the stairs.
+    pub fn defeat_boss(&mut self, kind: BossKind, pos: Pos) {
+        let loot = kind.loot();
+        let spot = if self.items.contains_key(&pos) {
+            self.free_tile_near(pos).unwrap_or(pos)
+        } else {
+            pos
+        };
+        self.emit(GameEvent::Info(format!(
+            "The {} drops {}",
+            kind.name(),
+            loot.name()
+        )));
+        self.items.insert(spot, loot);
+        if !self.exit_sealed() {
+            self.emit(GameEvent::Info("The stairs are unsealed".to_string()));
+        }
+    }
+    /// Deals this turn's damage from lingering statuses.
+    pub fn tick_statuses(&mut self) {
+        let mut events = vec![];
+        for status in self.player.statuses.tick() {
+            self.player.hp -= status.damage();
+            events.push(GameEvent::Attack {
+                attacker: Actor::Environment(status.source().to_string()),
+                target: Actor::Player,
+                amount: status.damage(),
+                pos: self.player.pos,
+            });
+        }
+        let mut positions: Vec<Pos> = self.enemies.keys().copied().collect();
+        positions.sort_by_key(|p| (p.1, p.0));
+        for pos in positions {
+            let enemy = self.enemies.get_mut(&pos).unwrap();
+            for status in enemy.statuses.tick() {
+                let attacker = Actor::Environment(status.source().to_string());
+                enemy.hit_by(status.damage(), &attacker);
+                events.push(GameEvent::Attack {
+                    attacker,
+                    target: Actor::Enemy(enemy.name().to_string()),
+                    amount: status.damage(),
+                    pos,
+                });
+            }
+        }
+        for event in events {
+            self.emit(event);
+        }
+    }
+    /// Fires the trap at `pos` on the player, or on `victim` if an enemy stepped on it.
+    pub fn spring_trap(&mut self, pos: Pos, victim: Option<&mut Enemy>) {
+        let Some(trap) = self.traps.get_mut(&pos) else {
+            return;
+        };
+        if victim.is_some() && trap.known_to_enemies {
+            return;
+        }
+        trap.known_to_enemies = true;
+        let kind = trap.kind;
+        let seen = victim.is_none() || self.compute_walls().contains(&pos);
+        if seen {
+            self.traps.get_mut(&pos).unwrap().revealed = true;
+        }
+        let actor = victim.as_ref().map_or(Actor::Player, |enemy| {
+            Actor::Enemy(enemy.name().to_string())
+        });
+        if seen {
+            self.emit(GameEvent::TrapSprung {
+                trap: kind.name().to_string(),
+                actor: actor.clone(),
+                pos,
+            });
+        }
+        match kind {
+            TrapKind::PressurePlate => {
+                self.make_noise(pos, TRAP_NOISE);
+                self.close_doors(pos);
+            }
+            TrapKind::Dart => {
+                self.make_noise(pos, TRAP_NOISE);
+                let amount = self.rng.gen_range(DART_DAMAGE);
+                let dart = Actor::Environment("a dart trap".to_string());
+                match victim {
+                    Some(enemy) => enemy.hit_by(amount, &dart),
+                    None => self.player.hp -= amount,
+                }
+                if seen {
+                    self.emit(GameEvent::Attack {
+                        attacker: dart,
+                        target: actor,
+                        amount,
+                        pos,
+                    });
+                }
+            }
+            TrapKind::Alarm => self.make_noise(pos, ALARM_RADIUS),
+            TrapKind::Teleport(teleporter) => {
+                let Some(to) = self.teleport_destination(&teleporter) else {
+                    return;
+                };
+                match victim {
+                    Some(enemy) if !self.enemies.contains_key(&to) => {
+                        enemy.pos = to;
+                        self.emit(GameEvent::Move {
+                            actor,
+                            from: pos,
+                            to,
+                        });
+                    }
+                    Some(_) => {}
+                    None => {
+                        self.player.pos = to;
+                        self.emit(GameEvent::Teleport { from: pos, to });
+                    }
+                }
+            }
+        }
+    }
+    fn close_doors(&mut self, pos: Pos) {
+        for (p, block) in self.map.iter_mut() {
+            if *block == Block::Door(Door::Open)
+                && distance(*p, pos) <= PLATE_RADIUS
+                && *p != self.player.pos
+                && !self.enemies.contains_key(p)
+            {
+                *block = Block::Door(Door::Closed);
+            }
+        }
+    }
+    /// Rolls to reveal hidden traps around the player, returning how many were found.
+    pub fn search(&mut self, searching: bool) -> usize {
+        let radius = if searching {
+            SEARCH_RADIUS
+        } else {
+            NOTICE_RADIUS
+        };
+        let mut hidden: Vec<Pos> = self
+            .traps
+            .iter()
+            .filter(|(p, t)| !t.revealed && distance(**p, self.player.pos) <= radius)
+            .map(|(p, _)| *p)
+            .collect();
+        hidden.sort_by_key(|p| (p.1, p.0));
+        let mut found = 0;
+        for pos in hidden {
+            let trap = self.traps.get_mut(&pos).unwrap();
+            if self
+                .rng
+                .gen_bool(trap.detect_chance(self.player.luck(), searching))
+            {
+                trap.revealed = true;
+                let trap = trap.kind.name().to_string();
+                self.emit(GameEvent::TrapFound { trap, pos });
+                found += 1;
+            }
+        }
+        found
     }
-    pub fn event(&mut self, event: String, etype: EventType) {
-        self.log.push_front((event, etype));
+    pub fn disarm(&mut self) -> bool {
+        let Some(pos) = self
+            .player
+            .pos
+            .around()
+            .into_iter()
+            .find(|p| self.traps.get(p).is_some_and(|t| t.revealed))
+        else {
+            self.emit(GameEvent::Info("There is no known trap nearby".to_string()));
+            return false;
+        };
+        let chance = self.traps[&pos].disarm_chance(self.player.luck());
+        if self.rng.gen_bool(chance) {
+            let trap = self.traps.remove(&pos).unwrap().kind.name().to_string();
+            self.emit(GameEvent::TrapDisarmed { trap, pos });
+        } else {
+            self.emit(GameEvent::Info("You fumble the mechanism".to_string()));
+            self.spring_trap(pos, None);
+        }
+        true
+    }
+    pub fn pick_up(&mut self) -> bool {
+        if let Some(item) = self.items.remove(&self.player.pos) {
+            self.emit(GameEvent::PickUp {
+                item: item.name().clone(),
+                pos: self.player.pos,
+            });
+            self.player.items.push(item);
+            return true;
+        }
+        false
+    }
+    pub fn equip(&mut self, i: usize) -> bool {
+        if self.player.equip(i).is_err() {
+            return false;
+        }
+        self.emit(GameEvent::Equip {
+            item: self.player.carrying.name().clone(),
+        });
+        true
+    }
+    pub fn open_door(&mut self, pos: Pos) -> bool {
+        let key = match self.map.get(&pos) {
+            Some(&Block::Door(Door::Closed)) => None,
+            Some(&Block::Door(Door::Locked(id))) => match self.player.take_key(id) {
+                Some(key) => Some(key.name().clone()),
+                None => {
+                    self.emit(GameEvent::Info("The door is locked".to_string()));
+                    return false;
+                }
+            },
+            _ => return false,
+        };
+        open_door(&mut self.map, pos);
+        self.make_noise(pos, DOOR_NOISE);
+        self.emit(GameEvent::DoorOpened { pos, key });
+        true
+    }
+    /// The free tile next to the pad that `pos` links to.
+    pub fn teleport_destination(&self, pos: &Pos) -> Option<Pos> {
+        let Some(&Block::Teleporter(target)) = self.teleporters_map.get(pos) else {
+            return None;
+        };
+        self.free_tile_near(target)
+    }
+    /// The first tile around `center` something can be put down on safely.
+    pub fn free_tile_near(&self, center: Pos) -> Option<Pos> {
+        center.around().into_iter().find(|p| {
+            *p != center
+                && *p != self.player.pos
+                && self.in_bounds(p)
+                && self
+                    .map
+                    .get(p)
+                    .is_none_or(|b| *b == Block::Door(Door::Open))
+                && !self.teleporters_map.contains_key(p)
+                && !self.enemies.contains_key(p)
+                && !matches!(self.terrain.get(p), Some(Terrain::Lava | Terrain::Chasm))
+        })
+    }
+    /// Sends the player, or `victim` if an enemy stepped on it, through the pad at `pos`.
+    pub fn use_teleporter(&mut self, pos: Pos, victim: Option<&mut Enemy>) {
+        let turn = self.stats.turns;
+        let Some(pad) = self.pads.get_mut(&pos) else {
+            return;
+        };
+        if !pad.ready(turn) {
+            if victim.is_none() && !pad.active && !pad.one_way {
+                pad.active = true;
+                self.emit(GameEvent::Info("The teleporter hums to life".to_string()));
+            } else if let Some(status) = pad.status(turn).filter(|_| victim.is_none()) {
+                self.emit(GameEvent::Info(format!("The teleporter is {}", status)));
+            }
+            return;
+        }
+        let Some(to) = self.teleport_destination(&pos) else {
+            if victim.is_none() {
+                self.emit(GameEvent::Info("The teleporter fizzles".to_string()));
+            }
+            return;
+        };
+        if let Some(&Block::Teleporter(target)) = self.teleporters_map.get(&pos) {
+            for p in [pos, target] {
+                if let Some(pad) = self.pads.get_mut(&p) {
+                    pad.ready_at = turn + COOLDOWN;
+                }
+            }
+        }
+        match victim {
+            Some(enemy) => {
+                enemy.pos = to;
+                let actor = Actor::Enemy(enemy.name().to_string());
+                self.emit(GameEvent::Move {
+                    actor,
+                    from: pos,
+                    to,
+                });
+            }
+            None => {
+                self.player.pos = to;
+                self.emit(GameEvent::Teleport { from: pos, to });
+            }
+        }
+    }
+    pub fn describe_teleporter(&self, pos: &Pos) -> Option<String> {
+        let Some(&Block::Teleporter(target)) = self.teleporters_map.get(pos) else {
+            return None;
+        };
+        let pad = self.pads.get(pos).copied().unwrap_or_default();
+        let status = pad
+            .status(self.stats.turns)
+            .map_or(String::new(), |s| format!(" ({})", s));
+        Some(format!(
+            "Teleporter to {}, {}{}",
+            target.0, target.1, status
+        ))
+    }
+    pub fn known_teleporters(&self) -> Vec<Pos> {
+        let mut known: Vec<Pos> = self
+            .teleporters_map
+            .keys()
+            .filter(|pos| self.explored.contains(pos))
+            .copied()
+            .collect();
+        known.sort_by_key(|pos| (pos.1, pos.0));
+        known
+    }
+    pub fn dimensions(&self) -> (u32, u32) {
+        self.dungeon
+            .as_ref()
+            .map_or((0, 0), |d| d.params.dimensions)
+    }
+    pub fn in_bounds(&self, pos: &Pos) -> bool {
+        let (w, h) = self.dimensions();
+        (0..w as isize).contains(&pos.0) && (0..h as isize).contains(&pos.1)
     }
     pub fn compute_walls(&self) -> Vec<Pos> {
         let mut fov: Vec<Pos> = vec![];
-        let mut is_visible = |pos: SPos| fov.push(pos.into());
+        let mut is_visible = |pos: SPos| {
+            if self.light.is_lit(&pos.into()) {
+                fov.push(pos.into());
+            }
+        };
         let mut v = |pos: SPos| {
             distance(pos.into(), self.player.pos) > self.player.distance || {
-                self.map.get(&pos.into()) == Some(&Block::Wall)
+                self.blocks_sight(&pos.into())
             }
         };
         compute_fov(self.player.pos.as_tuple(), &mut v, &mut is_visible);
@@ -62,13 +729,13 @@ impl<'a> State<'a> {
     pub fn compute_items(&self) -> Vec<Pos> {
         let mut fov = vec![];
         let mut is_visible = |pos: SPos| {
-            if self.items.contains_key(&pos.into()) {
+            if self.items.contains_key(&pos.into()) && self.light.is_lit(&pos.into()) {
                 fov.push(pos.into());
             }
         };
         let mut v = |pos: SPos| {
             distance(pos.into(), self.player.pos) > self.player.distance || {
-                self.map.get(&pos.into()) == Some(&Block::Wall)
+                self.blocks_sight(&pos.into())
             }
         };
         compute_fov(self.player.pos.as_tuple(), &mut v, &mut is_visible);
@@ -78,32 +745,63 @@ impl<'a> State<'a> {
     pub fn compute_enemies(&self) -> Vec<Pos> {
         let mut fov = vec![];
         let mut is_visible = |pos: SPos| {
-            if self.enemies.contains_key(&pos.into()) {
+            if self.enemies.contains_key(&pos.into()) && self.light.is_lit(&pos.into()) {
                 fov.push(pos.into());
             }
         };
         let mut v = |pos: SPos| {
             distance(pos.into(), self.player.pos) > self.player.distance || {
-                self.map.get(&pos.into()) == Some(&Block::Wall)
+                self.blocks_sight(&pos.into())
             }
         };
         compute_fov(self.player.pos.as_tuple(), &mut v, &mut is_visible);
         fov
     }
 
+    pub fn new_run(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+        *self.player = Player::new((1, 1).into(), &mut self.rng);
+        self.seed = seed;
+        self.depth = 1;
+        self.stats = RunStats::default();
+        self.log.clear();
+        self.reset();
+    }
+
+    pub fn descend(&mut self) -> bool {
+        if self.depth >= FINAL_DEPTH {
+            return true;
+        }
+        self.depth += 1;
+        self.reset();
+        self.emit(GameEvent::Descend { depth: self.depth });
+        false
+    }
+
+    fn floor_seed(&self) -> [u8; 32] {
+        let mut seed = [0; 32];
+        seed[..8].copy_from_slice(&self.seed.to_le_bytes());
+        seed[8..12].copy_from_slice(&self.depth.to_le_bytes());
+        seed
+    }
+
     pub fn reset(&mut self) {
         self.path = BoundedVecDeque::new(300);
-        self.log = BoundedVecDeque::new(8);
+        self.noises.clear();
+        self.explored.clear();
+        self.exit_distance_turn = None;
         let params = GenerateDungeonParams {
             max_enemies_per_room: 1,
             squareness: 0.1,
             min_teleporters_per_floor: 10,
             max_teleporters_per_floor: 15,
             num_floors: 1,
+            min_traps_per_room: 0,
+            max_traps_per_room: 1,
             dimensions: (32, 32),
             ..Default::default()
         };
-        let dungeon = Dungeon::generate_with_params(params);
+        let dungeon = Dungeon::generate_with_seed_and_params(self.floor_seed(), params);
         self.player.pos = (
             dungeon.player_position.x as isize,
             dungeon.player_position.y as isize,
@@ -112,11 +810,24 @@ impl<'a> State<'a> {
         let floor = &dungeon.floors[0];
         self.dungeon = Some(dungeon.clone());
         let mut map = HashMap::new();
+        let doors: Vec<Vec<Pos>> = floor
+            .doors
+            .iter()
+            .map(|d| {
+                [(0, 0), (1, 0), (0, 1), (1, 1)]
+                    .into_iter()
+                    .map(|(x, y)| Pos(d.x as isize + x, d.y as isize + y))
+                    .filter(|p| floor.tiles[p.0 as usize][p.1 as usize] == Tile::Floor)
+                    .collect()
+            })
+            .collect();
+        let door_tiles: HashSet<Pos> = doors.iter().flatten().copied().collect();
         let mut farthes = f32::MIN;
         let mut far_pos = (farthes as isize, farthes as isize);
         for (x, col) in floor.tiles.iter().enumerate() {
             for (y, tile) in col.iter().enumerate() {
                 match tile {
+                    Tile::Floor if door_tiles.contains(&Pos(x as isize, y as isize)) => {}
                     Tile::Floor => {
                         let dis = distance(self.player.pos, (x as isize, y as isize).into());
                         if dis > farthes {
@@ -142,27 +853,218 @@ impl<'a> State<'a> {
             })
         }));
 
+        self.teleporters_map.clear();
         for (target, teleporter) in tps.values() {
             self.teleporters_map
                 .insert(*teleporter, Block::Teleporter(tps.get(target).unwrap().1));
         }
-        let enemies: HashMap<Pos, Enemy> = HashMap::from_iter(floor.rooms.iter().flat_map(|r| {
-            r.enemies.iter().step_by(2).map(|enemy| {
+        self.place_pads();
+        let enemies: Vec<Enemy> = floor
+            .rooms
+            .iter()
+            .flat_map(|r| r.enemies.iter().step_by(2))
+            .map(|enemy| {
                 let p = (enemy.position.x as isize, enemy.position.y as isize).into();
-                (p, Enemy::new(32, p, enemy.difficulty))
+                Enemy::new(32, p, enemy.difficulty, &mut self.rng)
             })
-        }));
-        self.items = HashMap::<Pos, Item>::from_iter(floor.rooms.iter().flat_map(|r| {
-            r.items.iter().map(|i| {
+            .collect();
+        self.items = HashMap::<Pos, Item>::from_iter(
+            floor.rooms.iter().flat_map(|r| r.items.iter()).map(|i| {
                 (
                     Pos::from((i.position.x as isize, i.position.y as isize)),
-                    rand::random(),
+                    self.rng.gen(),
                 )
-            })
-        }));
+            }),
+        );
         map.insert(far_pos.into(), Block::Exit);
         self.exit = far_pos.into();
         self.map = map;
-        self.enemies = enemies;
+        self.enemies.clear();
+        for enemy in enemies {
+            self.spawn(enemy);
+        }
+        let mut locks = 0;
+        for door in &doors {
+            let state = if self.rng.gen_bool(LOCK_CHANCE) {
+                locks += 1;
+                Door::Locked(locks)
+            } else {
+                Door::Closed
+            };
+            for &p in door {
+                self.map.insert(p, Block::Door(state));
+            }
+        }
+        self.place_traps(floor);
+        self.place_terrain(floor);
+        self.place_keys(locks);
+        self.place_boss(floor);
+        self.braziers = floor
+            .rooms
+            .iter()
+            .filter(|r| matches!(r.kind, RoomKind::Main))
+            .map(|r| {
+                Pos(
+                    (r.position.x + r.width / 2) as isize,
+                    (r.position.y + r.height / 2) as isize,
+                )
+            })
+            .filter(|p| {
+                !self.items.contains_key(p)
+                    && !self.teleporters_map.contains_key(p)
+                    && !self.terrain.contains_key(p)
+                    && *p != self.exit
+            })
+            .filter(|_| self.rng.gen_bool(0.5))
+            .collect();
+        self.light = LightMap::compute(self);
+    }
+
+    fn place_pads(&mut self) {
+        let mut teleporters: Vec<Pos> = self.teleporters_map.keys().copied().collect();
+        teleporters.sort_by_key(|p| (p.1, p.0));
+        self.pads = teleporters.iter().map(|&p| (p, Pad::default())).collect();
+        for pos in teleporters {
+            let Some(&Block::Teleporter(target)) = self.teleporters_map.get(&pos) else {
+                continue;
+            };
+            // Decide each link once, from its first pad in reading order.
+            if (target.1, target.0) < (pos.1, pos.0) {
+                continue;
+            }
+            if self.rng.gen_bool(ONE_WAY_CHANCE) {
+                self.pads.get_mut(&target).unwrap().one_way = true;
+            }
+            if self.rng.gen_bool(DORMANT_CHANCE) {
+                self.pads.get_mut(&pos).unwrap().active = false;
+            }
+        }
+    }
+    fn place_traps(&mut self, floor: &Floor) {
+        let mut teleporters: Vec<Pos> = self.teleporters_map.keys().copied().collect();
+        teleporters.sort_by_key(|p| (p.1, p.0));
+        self.traps.clear();
+        for trap in floor.rooms.iter().flat_map(|r| r.traps.iter()) {
+            let pos = Pos(trap.position.x as isize, trap.position.y as isize);
+            if pos == self.player.pos
+                || self.map.contains_key(&pos)
+                || self.teleporters_map.contains_key(&pos)
+            {
+                continue;
+            }
+            let kind = match self.rng.gen_range(0..4) {
+                0 => TrapKind::PressurePlate,
+                1 => TrapKind::Dart,
+                2 => TrapKind::Alarm,
+                _ if teleporters.is_empty() => TrapKind::Dart,
+                _ => TrapKind::Teleport(teleporters[self.rng.gen_range(0..teleporters.len())]),
+            };
+            let known = self.rng.gen_bool(0.5);
+            self.traps
+                .insert(pos, Trap::new(kind, trap.difficulty, known));
+        }
+    }
+
+    fn place_terrain(&mut self, floor: &Floor) {
+        self.terrain.clear();
+        // No falling past a boss: its floor only ends through the sealed stairs.
+        let boss_floor = BossKind::for_depth(self.depth).is_some();
+        let kinds: Vec<Terrain> = Terrain::ALL
+            .into_iter()
+            .filter(|t| *t != Terrain::Chasm || !boss_floor)
+            .collect();
+        for room in floor
+            .rooms
+            .iter()
+            .filter(|r| matches!(r.kind, RoomKind::Main))
+        {
+            // Keep a one tile margin so the walls and doors stay reachable.
+            let (inner_w, inner_h) = (room.width.saturating_sub(4), room.height.saturating_sub(4));
+            if inner_w < 2 || inner_h < 2 || !self.rng.gen_bool(TERRAIN_CHANCE) {
+                continue;
+            }
+            let kind = kinds[self.rng.gen_range(0..kinds.len())];
+            let w = self.rng.gen_range(1..=inner_w / 2);
+            let h = self.rng.gen_range(1..=inner_h / 2);
+            let x0 = room.position.x + 2 + self.rng.gen_range(0..=inner_w - w);
+            let y0 = room.position.y + 2 + self.rng.gen_range(0..=inner_h - h);
+            for x in x0..x0 + w {
+                for y in y0..y0 + h {
+                    let pos = Pos(x as isize, y as isize);
+                    if pos != self.player.pos
+                        && !self.map.contains_key(&pos)
+                        && !self.teleporters_map.contains_key(&pos)
+                        && !self.items.contains_key(&pos)
+                        && !self.enemies.contains_key(&pos)
+                        && !self.traps.contains_key(&pos)
+                    {
+                        self.terrain.insert(pos, kind);
+                    }
+                }
+            }
+        }
+    }
+
+    /// On milestone floors the room holding the exit becomes a lair with a boss guarding the stairs.
+    fn place_boss(&mut self, floor: &Floor) {
+        self.lair.clear();
+        let Some(kind) = BossKind::for_depth(self.depth) else {
+            return;
+        };
+        let exit = self.exit;
+        if let Some(room) = floor.rooms.iter().find(|r| {
+            (r.position.x..r.position.x + r.width).contains(&(exit.0 as u32))
+                && (r.position.y..r.position.y + r.height).contains(&(exit.1 as u32))
+        }) {
+            self.lair = (room.position.y..room.position.y + room.height)
+                .flat_map(|y| {
+                    (room.position.x..room.position.x + room.width)
+                        .map(move |x| Pos(x as isize, y as isize))
+                })
+                .filter(|p| !self.map.contains_key(p))
+                .collect();
+        }
+        let Some(pos) = self.free_tile_near(exit) else {
+            return;
+        };
+        let boss = Enemy::boss(kind, pos, self.depth * 10, &mut self.rng);
+        self.spawn(boss);
+    }
+
+    /// Keys go on tiles reachable from the start without passing any locked door.
+    fn place_keys(&mut self, count: u32) {
+        let mut reachable = vec![self.player.pos];
+        let mut seen = HashSet::from([self.player.pos]);
+        let mut queue = VecDeque::from([self.player.pos]);
+        while let Some(pos) = queue.pop_front() {
+            for next in pos.adjacent() {
+                let open = match self.map.get(&next) {
+                    None | Some(Block::Door(Door::Closed)) => {
+                        self.in_bounds(&next) && !self.teleporters_map.contains_key(&next)
+                    }
+                    _ => false,
+                };
+                if open && seen.insert(next) {
+                    reachable.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        reachable.retain(|p| {
+            *p != self.player.pos
+                && !self.map.contains_key(p)
+                && !self.items.contains_key(p)
+                && !self.enemies.contains_key(p)
+                && !self.teleporters_map.contains_key(p)
+                && !self.traps.contains_key(p)
+                && !self.terrain.contains_key(p)
+        });
+        for id in 1..=count {
+            if reachable.is_empty() {
+                break;
+            }
+            let pos = reachable.swap_remove(self.rng.gen_range(0..reachable.len()));
+            self.items.insert(pos, Item::key(id));
+        }
     }
 }
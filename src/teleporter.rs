@@ -0,0 +1,44 @@
+pub const COOLDOWN: u32 = 5;
+pub const ONE_WAY_CHANCE: f64 = 0.2;
+pub const DORMANT_CHANCE: f64 = 0.25;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pad {
+    /// Receive-only end of a one-way link.
+    pub one_way: bool,
+    /// Dormant pads wake up the first time the player steps on them.
+    pub active: bool,
+    pub ready_at: u32,
+}
+
+impl Default for Pad {
+    fn default() -> Self {
+        Self {
+            one_way: false,
+            active: true,
+            ready_at: 0,
+        }
+    }
+}
+
+impl Pad {
+    pub fn sends(&self) -> bool {
+        self.active && !self.one_way
+    }
+
+    pub fn ready(&self, turn: u32) -> bool {
+        self.sends() && self.ready_at <= turn
+    }
+
+    pub fn status(&self, turn: u32) -> Option<String> {
+        if self.one_way {
+            Some("receive only".to_string())
+        } else if !self.active {
+            Some("dormant".to_string())
+        } else if self.ready_at > turn {
+            Some(format!("recharging for {} turns", self.ready_at - turn))
+        } else {
+            None
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use raylib::prelude::Color;
+
+pub const LAVA_DAMAGE: i32 = 10;
+pub const FALL_DAMAGE: i32 = 5;
+pub const TERRAIN_CHANCE: f64 = 0.35;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Terrain {
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Chasm,
+    Rubble,
+}
+
+impl Terrain {
+    pub const ALL: [Terrain; 5] = [
+        Terrain::ShallowWater,
+        Terrain::DeepWater,
+        Terrain::Lava,
+        Terrain::Chasm,
+        Terrain::Rubble,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Terrain::ShallowWater => "shallow water",
+            Terrain::DeepWater => "deep water",
+            Terrain::Lava => "lava",
+            Terrain::Chasm => "chasm",
+            Terrain::Rubble => "rubble",
+        }
+    }
+
+    /// Step cost for pathfinding, `None` where a walker should never go.
+    pub fn cost(&self) -> Option<u32> {
+        match self {
+            Terrain::ShallowWater | Terrain::Rubble => Some(2),
+            Terrain::DeepWater => Some(3),
+            Terrain::Lava => Some(10),
+            Terrain::Chasm => None,
+        }
+    }
+
+    pub fn blocks_sight(&self) -> bool {
+        matches!(self, Terrain::Rubble)
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Terrain::ShallowWater => Color::SKYBLUE,
+            Terrain::DeepWater => Color::DARKBLUE,
+            Terrain::Lava => Color::ORANGE,
+            Terrain::Chasm => Color::BLACK,
+            Terrain::Rubble => Color::BROWN,
+        }
+    }
+}
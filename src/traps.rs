@@ -0,0 +1,68 @@
+use std::ops::RangeInclusive;
+
+use raylib::prelude::Color;
+
+use crate::utils::Pos;
+
+pub const DART_DAMAGE: RangeInclusive<i32> = 5..=15;
+pub const ALARM_RADIUS: f32 = 10.0;
+pub const PLATE_RADIUS: f32 = 6.0;
+pub const NOTICE_RADIUS: f32 = 2.0;
+pub const SEARCH_RADIUS: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrapKind {
+    PressurePlate,
+    Dart,
+    Alarm,
+    /// Sends whoever steps on it through the linked teleporter.
+    Teleport(Pos),
+}
+
+impl TrapKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrapKind::PressurePlate => "pressure plate",
+            TrapKind::Dart => "dart trap",
+            TrapKind::Alarm => "alarm trap",
+            TrapKind::Teleport(_) => "teleport trap",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            TrapKind::PressurePlate => Color::LIGHTGRAY,
+            TrapKind::Dart => Color::RED,
+            TrapKind::Alarm => Color::YELLOW,
+            TrapKind::Teleport(_) => Color::VIOLET,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trap {
+    pub kind: TrapKind,
+    pub difficulty: u32,
+    pub revealed: bool,
+    pub known_to_enemies: bool,
+}
+
+impl Trap {
+    pub fn new(kind: TrapKind, difficulty: u32, known_to_enemies: bool) -> Self {
+        Self {
+            kind,
+            difficulty,
+            revealed: false,
+            known_to_enemies,
+        }
+    }
+
+    pub fn detect_chance(&self, luck: i32, searching: bool) -> f64 {
+        let base = if searching { luck * 4 } else { luck };
+        (base as f64 / 100.0 - self.difficulty as f64 / 400.0).clamp(0.01, 0.95)
+    }
+
+    pub fn disarm_chance(&self, luck: i32) -> f64 {
+        (0.5 + luck as f64 / 50.0 - self.difficulty as f64 / 200.0).clamp(0.05, 0.95)
+    }
+}
@@ -0,0 +1,190 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    bindings::{Bindings, GameAction},
+    dijkstra::DijkstraMap,
+    state::State,
+    teleporter::Pad,
+    terrain::Terrain,
+    utils::{Door, Pos},
+    Block,
+};
+
+const LOW_HP_PERCENT: i32 = 30;
+const STEP_DELAY: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Travel {
+    Explore,
+    Exit,
+    Teleporter(Pos),
+}
+
+impl Travel {
+    fn goal(&self, state: &State) -> Option<Pos> {
+        match self {
+            Travel::Explore => None,
+            Travel::Exit => Some(state.exit),
+            Travel::Teleporter(pos) => Some(*pos),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Interrupt {
+    Enemy,
+    LowHp,
+    NewItem,
+    Explored,
+    Arrived,
+    NoPath,
+}
+
+impl Interrupt {
+    pub fn message(&self, bindings: &Bindings) -> String {
+        match self {
+            Interrupt::Enemy => "An enemy comes into view".to_string(),
+            Interrupt::LowHp => "You are too hurt to keep going".to_string(),
+            Interrupt::NewItem => "You spot an item".to_string(),
+            Interrupt::Explored => format!(
+                "Floor explored, press ({}) to travel to the exit",
+                bindings.label(GameAction::TravelExit)
+            ),
+            Interrupt::Arrived => "You arrived".to_string(),
+            Interrupt::NoPath => "You don't know the way there".to_string(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct AutoMove {
+    pub travel: Travel,
+    seen_items: HashSet<Pos>,
+    delay: u32,
+    arriving: bool,
+}
+
+impl AutoMove {
+    pub fn new(travel: Travel, state: &State) -> Self {
+        Self {
+            travel,
+            seen_items: HashSet::from_iter(state.compute_items()),
+            delay: 0,
+            arriving: false,
+        }
+    }
+
+    pub fn next_step(
+        &mut self,
+        state: &State,
+        auto_pickup: bool,
+    ) -> Result<Option<Pos>, Interrupt> {
+        if !state.compute_enemies().is_empty() {
+            return Err(Interrupt::Enemy);
+        }
+        if state.player.hp * 100 < state.player.max_hp * LOW_HP_PERCENT {
+            return Err(Interrupt::LowHp);
+        }
+        let items = state.compute_items();
+        if !auto_pickup && items.iter().any(|p| !self.seen_items.contains(p)) {
+            return Err(Interrupt::NewItem);
+        }
+        self.seen_items.extend(items);
+        if self.delay > 0 {
+            self.delay -= 1;
+            return Ok(None);
+        }
+        self.delay = STEP_DELAY;
+        if self.arriving {
+            return Err(Interrupt::Arrived);
+        }
+
+        let goal = self.travel.goal(state);
+        let pos = state.player.pos;
+        let mut map = match goal {
+            None => explore_map(state, auto_pickup, None),
+            Some(goal) => travel_map(state, goal),
+        };
+        // Standing on an explore goal, so head for the next one instead.
+        if goal.is_none() && map.get(&pos) == Some(0) {
+            map = explore_map(state, auto_pickup, Some(pos));
+        }
+        match map.downhill(pos, pos.adjacent()) {
+            Some(next) => {
+                self.arriving = Some(next) == goal;
+                Ok(Some(next - pos))
+            }
+            None if goal.is_none() && map.is_empty() => Err(Interrupt::Explored),
+            None if map.get(&pos) == Some(0) => Err(Interrupt::Arrived),
+            None => Err(Interrupt::NoPath),
+        }
+    }
+}
+
+fn passable(state: &State, pos: &Pos) -> bool {
+    state.explored.contains(pos)
+        && match state.map.get(pos) {
+            Some(Block::Wall) => false,
+            Some(Block::Door(Door::Locked(key))) => state.player.has_key(*key),
+            _ => true,
+        }
+        && !state.teleporters_map.contains_key(pos)
+        && !state.traps.get(pos).is_some_and(|t| t.revealed)
+        && !matches!(state.terrain.get(pos), Some(Terrain::Lava | Terrain::Chasm))
+}
+
+fn successors<'a>(state: &'a State) -> impl FnMut(Pos) -> Vec<(Pos, u32)> + 'a {
+    move |pos: Pos| {
+        pos.adjacent()
+            .into_iter()
+            .filter(|p| passable(state, p))
+            .map(|p| {
+                (
+                    p,
+                    state.terrain.get(&p).and_then(Terrain::cost).unwrap_or(1),
+                )
+            })
+            .collect()
+    }
+}
+
+fn explore_map(state: &State, auto_pickup: bool, skip: Option<Pos>) -> DijkstraMap {
+    let frontier = state.explored.iter().filter(|pos| {
+        Some(**pos) != skip
+            && passable(state, pos)
+            && pos
+                .adjacent()
+                .iter()
+                .any(|p| state.in_bounds(p) && !state.explored.contains(p))
+    });
+    let items = state
+        .items
+        .keys()
+        .filter(|pos| auto_pickup && Some(**pos) != skip && state.explored.contains(pos));
+    DijkstraMap::new(frontier.chain(items).copied(), successors(state))
+}
+
+fn travel_map(state: &State, goal: Pos) -> DijkstraMap {
+    let mut links: HashMap<Pos, Vec<Pos>> = HashMap::new();
+    for teleporter in state.known_teleporters() {
+        if !state.pads.get(&teleporter).is_some_and(Pad::sends) {
+            continue;
+        }
+        if let Some(landing) = state.teleport_destination(&teleporter) {
+            links.entry(landing).or_default().push(teleporter);
+        }
+    }
+    let mut walk = successors(state);
+    let goals = state.explored.contains(&goal).then_some(goal);
+    DijkstraMap::new(goals, move |pos| {
+        let mut out = walk(pos);
+        if let Some(teleporters) = links.get(&pos) {
+            out.extend(teleporters.iter().map(|&t| (t, 1)));
+        }
+        out
+    })
+}
+
+pub fn travel_distance(state: &State, goal: Pos) -> Option<u32> {
+    travel_map(state, goal).get(&state.player.pos)
+}
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
+
 use symmetric_shadowcasting::Pos as SymPos;
 
-use crate::state::State;
+use crate::{state::State, terrain::Terrain};
 
 #[derive(Debug, Clone, Copy, PartialEq, Hash, Eq)]
 pub struct Pos(pub isize, pub isize);
@@ -14,6 +16,14 @@ impl Pos {
             .flat_map(|y| (-1..=1).map(move |x| Self::from((self.0 + x, self.1 + y))))
             .collect()
     }
+    pub fn adjacent(&self) -> [Self; 4] {
+        [
+            Self(self.0, self.1 - 1),
+            Self(self.0 + 1, self.1),
+            Self(self.0, self.1 + 1),
+            Self(self.0 - 1, self.1),
+        ]
+    }
 }
 
 impl From<(isize, isize)> for Pos {
@@ -30,24 +40,95 @@ impl std::ops::Add for Pos {
     }
 }
 
+impl std::ops::Sub for Pos {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        (self.0 - rhs.0, self.1 - rhs.1).into()
+    }
+}
+
 pub fn distance(a: Pos, b: Pos) -> f32 {
     ((a.0 - b.0).pow(2) as f32 + (a.1 - b.1).pow(2) as f32).sqrt()
 }
 
+/// Tiles on the Bresenham line from `a` to `b`, both ends included.
+pub fn line(a: Pos, b: Pos) -> Vec<Pos> {
+    let (dx, dy) = ((b.0 - a.0).abs(), -(b.1 - a.1).abs());
+    let (sx, sy) = ((b.0 - a.0).signum(), (b.1 - a.1).signum());
+    let mut err = dx + dy;
+    let mut pos = a;
+    let mut out = vec![a];
+    while pos != b {
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            pos.0 += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            pos.1 += sy;
+        }
+        out.push(pos);
+    }
+    out
+}
+
 pub fn check_collision(state: &mut State, delta: &Pos) {
     let newpos = &(state.player.pos + *delta);
     match state.map.get(newpos) {
         Some(Block::Wall) => {}
+        Some(Block::Door(Door::Closed | Door::Locked(_))) => {
+            state.open_door(*newpos);
+        }
         _ => {
+            let wading = state.terrain.get(&state.player.pos) == Some(&Terrain::ShallowWater);
+            if wading && !state.player.wading {
+                state.player.wading = true;
+                return;
+            }
+            state.player.wading = false;
             state.player.cicle_swing();
             state.player.pos = *newpos;
         }
     }
 }
 
+/// Opens the door at `pos` along with the adjacent tiles in the same state, so one key
+/// never opens a neighbouring door it doesn't belong to.
+pub fn open_door(map: &mut HashMap<Pos, Block>, pos: Pos) {
+    let Some(&Block::Door(door @ (Door::Closed | Door::Locked(_)))) = map.get(&pos) else {
+        return;
+    };
+    let mut stack = vec![pos];
+    while let Some(pos) = stack.pop() {
+        if let Some(block) = map.get_mut(&pos).filter(|b| **b == Block::Door(door)) {
+            *block = Block::Door(Door::Open);
+            stack.extend(pos.adjacent());
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+pub enum Door {
+    Open,
+    Closed,
+    Locked(u32),
+}
+
 #[derive(PartialEq, Debug)]
 pub enum Block {
     Wall,
     Exit,
     Teleporter(Pos),
+    Door(Door),
+}
+
+impl Block {
+    pub fn blocks_sight(&self) -> bool {
+        matches!(
+            self,
+            Block::Wall | Block::Door(Door::Closed | Door::Locked(_))
+        )
+    }
 }